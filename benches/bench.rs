@@ -47,7 +47,7 @@ fn bench_crossover(c: &mut Criterion) {
 
         let cross_points = [1, n / 4, n / 2, 3 * (n / 4)];
 
-        let result_pair = cross_at_points_im(pair, &cross_points);
+        cross_at_points_im(pair, &cross_points)
     }));
 }
 
@@ -112,7 +112,7 @@ fn bench_point_mutation(c: &mut Criterion) {
         let mut ind = Ind(iter::repeat(0x0).take(n).collect());
         let pm = 0.01;
         let mut rng = thread_rng();
-        point_mutate_naive(&mut ind, 4, pm, &mut rng);
+        point_mutate_naive(ind.0.iter_mut(), 4, pm, &mut rng);
     }));
 
     c.bench_function("point_mutation_100", |b| b.iter(|| {
@@ -136,7 +136,7 @@ fn bench_point_mutation(c: &mut Criterion) {
         let mut ind = Ind(iter::repeat(0x0).take(n).collect());
         let pm = 0.01;
         let mut rng = thread_rng();
-        point_mutate_naive(&mut ind, 4, pm, &mut rng);
+        point_mutate_naive(ind.0.iter_mut(), 4, pm, &mut rng);
     }));
 
     c.bench_function("point_mutation_1000", |b| b.iter(|| {
@@ -160,7 +160,7 @@ fn bench_point_mutation(c: &mut Criterion) {
         let mut ind = Ind(iter::repeat(0x0).take(n).collect());
         let pm = 0.01;
         let mut rng = thread_rng();
-        point_mutate_naive(&mut ind, 4, pm, &mut rng);
+        point_mutate_naive(ind.0.iter_mut(), 4, pm, &mut rng);
     }));
 
     c.bench_function("point_mutation_10000", |b| b.iter(|| {
@@ -186,8 +186,8 @@ fn bench_point_mutation_traversal(c: &mut Criterion) {
         let mut ind = Ind(iter::repeat(0x0).take(n).collect());
         let pm = 0.01;
         let mut rng = thread_rng();
-        point_mutate_naive(&mut ind, 4, pm, &mut rng);
-        point_mutate_naive(&mut ind, 4, pm, &mut rng);
+        point_mutate_naive(ind.0.iter_mut(), 4, pm, &mut rng);
+        point_mutate_naive(ind.0.iter_mut(), 4, pm, &mut rng);
     }));
 
     c.bench_function("point_mutation_likely", |b| b.iter(|| {
@@ -195,7 +195,7 @@ fn bench_point_mutation_traversal(c: &mut Criterion) {
         let mut ind = Ind(iter::repeat(0x0).take(n).collect());
         let pm = 0.02;
         let mut rng = thread_rng();
-        point_mutate_naive(&mut ind, 4, pm, &mut rng);
+        point_mutate_naive(ind.0.iter_mut(), 4, pm, &mut rng);
     }));
 }
 
@@ -225,6 +225,26 @@ fn bench_point_mutation_geometric(c: &mut Criterion) {
     }));
 }
 
+// point_mutate and point_mutate_masks side by side at the same rates and lengths. With 4 bit
+// symbols they cross near a pm of 0.03, which sets WORD_MUTATION_FLIPS for point_mutate_words
+fn bench_point_mutation_words(c: &mut Criterion) {
+    for pm in [0.001, 0.003, 0.01, 0.03, 0.1, 0.3] {
+        for n in [1000, 100000] {
+            c.bench_function(&format!("point_mutation_geometric_{}_{}", pm, n), move |b| {
+                let mut ind = Ind(iter::repeat(0x0u8).take(n).collect());
+                let mut rng = SmallRng::seed_from_u64(0);
+                b.iter(|| point_mutate(&mut ind, 4, pm, &mut rng))
+            });
+
+            c.bench_function(&format!("point_mutation_masks_{}_{}", pm, n), move |b| {
+                let mut ind = Ind(iter::repeat(0x0u8).take(n).collect());
+                let mut rng = SmallRng::seed_from_u64(0);
+                b.iter(|| point_mutate_masks(&mut ind, 4, pm, &mut rng))
+            });
+        }
+    }
+}
+
 fn bench_rgep_operators(c: &mut Criterion) {
     c.bench_function("point_mutation_operator", |b| b.iter(|| {
         let n = 10000;
@@ -274,9 +294,9 @@ fn bench_rgep_operators(c: &mut Criterion) {
         let alt_ind = Ind(iter::repeat(0x0).take(ind_len).collect());
         let mut alt_pop = Pop(iter::repeat(alt_ind).take(n).collect());
 
-        let fitnesses = (0..n).map(|f| f as f64).collect();
+        let fitnesses: Vec<f64> = (0..n).map(|f| f as f64).collect();
 
-        select_stochastic_universal(&pop, &mut alt_pop, fitnesses, 1, 0.5);
+        select_stochastic_universal(&pop, &mut alt_pop, &fitnesses, 1, 0.5);
     }));
 }
 
@@ -288,8 +308,8 @@ fn bench_selection(c: &mut Criterion) {
     let pop = Pop(iter::repeat(ind).take(n).collect());
 
     c.bench_function("select_stochastic_universal_naive", move |b| b.iter(|| {
-        let fitnesses = (0..n).map(|f| f as f64).collect();
-        select_stochastic_universal_naive(&pop, fitnesses, 1, 0.5);
+        let fitnesses: Vec<f64> = (0..n).map(|f| f as f64).collect();
+        select_stochastic_universal_naive(&pop, &fitnesses, 1, 0.5);
     }));
 
     let ind = Ind(iter::repeat(0x0).take(ind_len).collect());
@@ -298,12 +318,12 @@ fn bench_selection(c: &mut Criterion) {
     let mut new_pop = Pop(iter::repeat(empty_ind).take(n).collect());
 
     c.bench_function("select_stochastic_universal", move |b| b.iter(|| {
-        let fitnesses = (0..n).map(|f| f as f64).collect();
-        select_stochastic_universal(&pop, &mut new_pop, fitnesses, 1, 0.5);
+        let fitnesses: Vec<f64> = (0..n).map(|f| f as f64).collect();
+        select_stochastic_universal(&pop, &mut new_pop, &fitnesses, 1, 0.5);
     }));
 }
 
-criterion_group!(point_mutation, bench_point_mutation, bench_point_mutation_geometric, bench_point_mutation_traversal);
+criterion_group!(point_mutation, bench_point_mutation, bench_point_mutation_geometric, bench_point_mutation_traversal, bench_point_mutation_words);
 criterion_group!(crossover, bench_crossover);
 criterion_group!(rotation, bench_rotation_offsets, bench_rotation_sizes, bench_rotation_types);
 criterion_group!(rgep, bench_rgep_operators);
@@ -3,12 +3,11 @@ extern crate rand;
 
 use std::rc::Rc;
 
-use rand::prelude::*;
-use rand::rngs::SmallRng;
 
 use rgep::ga::*;
 use rgep::types::*;
 use rgep::evaluation::*;
+use rgep::observer::*;
 
 
 
@@ -16,8 +15,8 @@ fn main() {
     let mut params = GaParams::default();
     params.num_gens *= 10;
 
-    let eval: Eval<IndU8, SmallRng> =
-        Rc::new(|ind, rng| {
+    let eval: Eval<IndU8, R> =
+        Rc::new(|ind, _rng| {
             let mut sum: f64 = 0.0;
             for value in ind.0.iter() {
 
@@ -26,21 +25,8 @@ fn main() {
             return sum;
     });
 
-    let mut rng = SmallRng::from_entropy();
+    let result = ga_with_observer(&params, eval, &mut PrintObserver::new(1000));
 
-    let pop = ga(&params, eval, &mut rng);
-
-    let mut best = 0.0;
-    for ind in (*pop.borrow_mut()).0.iter() {
-        let mut current = 0.0;
-        for ix in ind.0.iter() {
-            current += *ix as f64;
-        }
-
-        if current > best {
-            best = current;
-        }
-    }
-
-    println!("best fitness = {}", best);
+    println!("best fitness = {}", result.best_fitness);
+    println!("stopped by {:?} after {} generations", result.stop_reason, result.fitness_history.len());
 }
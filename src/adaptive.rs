@@ -0,0 +1,215 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use rand::prelude::*;
+use rand::distributions::Distribution;
+
+use num::PrimInt;
+
+use statrs::distribution::Normal;
+
+use types::*;
+use stage::*;
+use point_mutation::*;
+
+
+// adapted mutation rates are kept within these bounds
+pub const MIN_MUTATION_RATE: f64 = 0.000001;
+pub const MAX_MUTATION_RATE: f64 = 0.5;
+
+// How the point mutation rate changes over a run, starting from the initial rate.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum MutationRate {
+    // the initial rate for the whole run
+    #[default]
+    Fixed,
+    // move linearly from the initial rate to final_rate over the run's generations
+    Linear { final_rate: f64 },
+    // multiply the initial rate by decay each generation
+    Exponential { decay: f64 },
+    // Rechenberg's 1/5th success rule. If more than a fifth of the offspring in a generation
+    // are fitter than their parents the rate is multiplied by factor, and if fewer it is
+    // divided by factor.
+    OneFifth { factor: f64 },
+    // each individual carries its own rate, which is mutated log-normally with the given
+    // learning rate before being used to mutate that individual. Runs that support it keep each
+    // rate with its individual using MutationRates::select, so offspring inherit their
+    // parents' rates.
+    SelfAdaptive { learning_rate: f64 },
+}
+
+pub fn clamp_rate(rate: f64) -> f64 {
+    rate.clamp(MIN_MUTATION_RATE, MAX_MUTATION_RATE)
+}
+
+// rate * exp(learning_rate * N(0, 1)), within the rate bounds
+pub fn log_normal_rate<R: Rng>(rate: f64, learning_rate: f64, rng: &mut R) -> f64 {
+    let step = Normal::new(0.0, 1.0).unwrap().sample(rng);
+    clamp_rate(rate * (learning_rate * step).exp())
+}
+
+// the number of individuals fitter than their parent, where parent_fitnesses[index] is the
+// fitness of the parent of the individual with fitnesses[index]
+pub fn count_successes(fitnesses: &[f64], parent_fitnesses: &[f64]) -> usize {
+    fitnesses.iter()
+             .zip(parent_fitnesses.iter())
+             .filter(|(fitness, parent_fitness)| fitness > parent_fitness)
+             .count()
+}
+
+// The mutation rates of a run. rate is used for the whole population, except with
+// MutationRate::SelfAdaptive, where rates holds a rate for each individual. Ind has no room
+// for metadata, so like the ages of an AlpsLayer the rates are kept parallel to the population,
+// and must follow the individuals through selection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MutationRates {
+    pub schedule: MutationRate,
+    pub initial_rate: f64,
+    pub rate: f64,
+    pub rates: Vec<f64>,
+}
+
+impl MutationRates {
+    pub fn new(schedule: MutationRate, initial_rate: f64, num_inds: usize) -> MutationRates {
+        let rates = match schedule {
+            MutationRate::SelfAdaptive { .. } => vec![initial_rate; num_inds],
+            _ => Vec::new(),
+        };
+
+        MutationRates {
+            schedule,
+            initial_rate,
+            rate: initial_rate,
+            rates,
+        }
+    }
+
+    // set the scheduled rate for the given generation of a run of num_gens generations
+    pub fn start_generation(&mut self, generation: usize, num_gens: usize) {
+        match self.schedule {
+            MutationRate::Linear { final_rate } => {
+                let fraction = (generation as f64 / num_gens.saturating_sub(1).max(1) as f64).min(1.0);
+                self.rate = clamp_rate(self.initial_rate + (final_rate - self.initial_rate) * fraction);
+            },
+
+            MutationRate::Exponential { decay } => {
+                self.rate = clamp_rate(self.initial_rate * decay.powi(generation as i32));
+            },
+
+            _ => {},
+        }
+    }
+
+    // record how many of a generation's offspring were fitter than their parents
+    pub fn record_successes(&mut self, successes: usize, num_offspring: usize) {
+        if let MutationRate::OneFifth { factor } = self.schedule {
+            if num_offspring > 0 {
+                let success_ratio = successes as f64 / num_offspring as f64;
+                if success_ratio > 0.2 {
+                    self.rate = clamp_rate(self.rate * factor);
+                } else if success_ratio < 0.2 {
+                    self.rate = clamp_rate(self.rate / factor);
+                }
+            }
+        }
+    }
+
+    // Keep each individual's rate with it when the population becomes a copy of the selected
+    // individuals, or when it is rearranged into the given order.
+    pub fn select(&mut self, selected: &[usize]) {
+        if !self.rates.is_empty() {
+            self.rates = selected.iter().map(|index| self.rates[*index]).collect();
+        }
+    }
+
+    // Start the individuals at the given indices from the current rate, as when they are
+    // copied into the population from outside it.
+    pub fn reset(&mut self, indices: &[usize]) {
+        for index in indices {
+            if *index < self.rates.len() {
+                self.rates[*index] = self.rate;
+            }
+        }
+    }
+
+    // Point mutate the population with the current rates. Self-adaptive rates are mutated
+    // first, and any individuals without a rate start from the current rate.
+    pub fn mutate<T: PrimInt, R: Rng>(&mut self, pop: &mut Pop<T>, bits_used: usize, rng: &mut R) {
+        match self.schedule {
+            MutationRate::SelfAdaptive { learning_rate } => {
+                self.rates.resize(pop.0.len(), self.rate);
+                for rate in self.rates.iter_mut() {
+                    *rate = log_normal_rate(*rate, learning_rate, rng);
+                }
+
+                point_mutation_rates(pop, bits_used, &self.rates, rng);
+            },
+
+            _ => {
+                point_mutation(pop, bits_used, self.rate, rng);
+            },
+        }
+    }
+}
+
+pub struct AdaptiveMutationState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub rates: Rc<RefCell<MutationRates>>,
+    pub bits_used: usize,
+}
+
+impl<T> AdaptiveMutationState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               rates: Rc<RefCell<MutationRates>>,
+               bits_used: usize) -> AdaptiveMutationState<T> {
+        AdaptiveMutationState { population, rates, bits_used }
+    }
+}
+
+// point mutation with the current rates, which are updated separately as the run goes on
+pub fn adaptive_mutation_stage<S, T, R>(getter: Getter<S, AdaptiveMutationState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let mutation_state = getter(state);
+        mutation_state.rates.borrow_mut().mutate(&mut mutation_state.population.borrow_mut(),
+                                                 mutation_state.bits_used,
+                                                 rng);
+    });
+
+    f
+}
+
+#[test]
+fn test_mutation_rates() {
+    let mut rates = MutationRates::new(MutationRate::Linear { final_rate: 0.0 }, 0.1, 10);
+    rates.start_generation(5, 11);
+    assert!((rates.rate - 0.05).abs() < 0.000001);
+    rates.start_generation(20, 11);
+    assert_eq!(rates.rate, MIN_MUTATION_RATE);
+
+    let mut rates = MutationRates::new(MutationRate::Exponential { decay: 0.5 }, 0.1, 10);
+    rates.start_generation(2, 100);
+    assert_eq!(rates.rate, 0.025);
+
+    let mut rates = MutationRates::new(MutationRate::OneFifth { factor: 2.0 }, 0.1, 10);
+    rates.record_successes(count_successes(&vec!(1.0, 2.0, 3.0, 4.0, 5.0), &vec!(0.0, 0.0, 3.0, 4.0, 5.0)), 5);
+    assert_eq!(rates.rate, 0.2);
+    rates.record_successes(0, 5);
+    rates.record_successes(0, 5);
+    assert_eq!(rates.rate, 0.05);
+
+    // self-adaptive rates stay with their individuals, and stay within bounds
+    let mut rng = R::seed_from_u64(0);
+    let mut rates = MutationRates::new(MutationRate::SelfAdaptive { learning_rate: 1.0 }, 0.1, 4);
+    let mut pop = Pop(vec![Ind(vec![0u8; 10]); 4]);
+    for _ in 0..20 {
+        rates.mutate(&mut pop, 4, &mut rng);
+        assert!(rates.rates.iter().all(|rate| *rate >= MIN_MUTATION_RATE && *rate <= MAX_MUTATION_RATE));
+    }
+
+    let before = rates.rates.clone();
+    rates.select(&[3, 3, 0, 1]);
+    assert_eq!(rates.rates, vec!(before[3], before[3], before[0], before[1]));
+}
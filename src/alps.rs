@@ -0,0 +1,114 @@
+use types::*;
+
+
+// How the maximum age of each ALPS layer grows, in multiples of the age gap.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum AgingScheme {
+    // 1, 2, 3, 4, 5, ...
+    Linear,
+    // 1, 2, 4, 9, 16, ...
+    #[default]
+    Polynomial,
+    // 1, 2, 4, 8, 16, ...
+    Exponential,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AlpsParams {
+    pub num_layers: usize,
+
+    // fresh individuals are injected into the bottom layer every age_gap generations
+    pub age_gap: usize,
+    pub aging: AgingScheme,
+}
+
+impl Default for AlpsParams {
+    fn default() -> AlpsParams {
+        AlpsParams {
+            num_layers: 5,
+            age_gap: 10,
+            aging: AgingScheme::default(),
+        }
+    }
+}
+
+impl AlpsParams {
+    // The oldest an individual in the given layer may be. The top layer has no limit.
+    pub fn age_limit(&self, layer: usize) -> Option<usize> {
+        if layer + 1 >= self.num_layers {
+            return None;
+        }
+
+        let multiple = match self.aging {
+            AgingScheme::Linear => layer + 1,
+            AgingScheme::Polynomial => if layer < 2 { layer + 1 } else { layer * layer },
+            AgingScheme::Exponential => 1 << layer,
+        };
+
+        Some(self.age_gap * multiple)
+    }
+}
+
+// A layer of an ALPS population. Ind has no room for metadata, so the age and fitness of
+// each individual are kept in vectors parallel to the population, as are their
+// self-adaptive mutation rates when there are any.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlpsLayer<T> {
+    pub pop: Pop<T>,
+    pub ages: Vec<usize>,
+    pub fitnesses: Vec<f64>,
+    pub rates: Vec<f64>,
+}
+
+impl<T> AlpsLayer<T> {
+    pub fn new() -> AlpsLayer<T> {
+        AlpsLayer {
+            pop: Pop(Vec::new()),
+            ages: Vec::new(),
+            fitnesses: Vec::new(),
+            rates: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pop.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pop.0.is_empty()
+    }
+}
+
+impl<T> Default for AlpsLayer<T> {
+    fn default() -> AlpsLayer<T> {
+        AlpsLayer::new()
+    }
+}
+
+// The ages of offspring copied from the selected individuals, where each pair of offspring
+// may be crossed with each other. Both take the age of the older of the pair, plus one.
+pub fn offspring_ages(ages: &[usize], selected: &[usize]) -> Vec<usize> {
+    let mut new_ages = Vec::with_capacity(selected.len());
+
+    for pair in selected.chunks(2) {
+        let age = pair.iter().map(|index| ages[*index]).max().unwrap() + 1;
+        new_ages.extend(pair.iter().map(|_| age));
+    }
+
+    new_ages
+}
+
+#[test]
+fn test_alps_ages() {
+    let alps = AlpsParams { num_layers: 4, age_gap: 5, aging: AgingScheme::Polynomial };
+    assert_eq!(alps.age_limit(0), Some(5));
+    assert_eq!(alps.age_limit(1), Some(10));
+    assert_eq!(alps.age_limit(2), Some(20));
+    assert_eq!(alps.age_limit(3), None);
+
+    let alps = AlpsParams { aging: AgingScheme::Exponential, ..alps };
+    assert_eq!(alps.age_limit(2), Some(20));
+
+    let ages = vec!(0, 3, 7);
+    assert_eq!(offspring_ages(&ages, &[0, 1, 2, 0, 1]), vec!(4, 4, 8, 8, 4));
+}
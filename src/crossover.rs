@@ -15,49 +15,106 @@ use types::*;
 use stage::*;
 
 
-pub struct CrossoverState {
-    pub population: Rc<RefCell<PopU8>>,
-    pub pc1: f64,
+// Where crossover and rotation may cut an individual. Each word holds one symbol in its
+// lowest bits_per_sym bits, so cuts only ever fall within those bits.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum CutMode {
+    // cut between any two used bits, even within a symbol
+    #[default]
+    Bit,
+    // cut only between whole symbols
+    Symbol,
 }
 
-impl CrossoverState {
-    pub fn new(population: Rc<RefCell<PopU8>>,
-               pc1: f64) -> CrossoverState {
-        return CrossoverState { population, pc1 };
+impl CutMode {
+    // the number of places an individual of words_per_ind symbols can be cut
+    pub fn num_cut_points(&self, words_per_ind: usize, bits_per_sym: usize) -> usize {
+        match self {
+            CutMode::Bit => words_per_ind * bits_per_sym,
+            CutMode::Symbol => words_per_ind,
+        }
+    }
+
+    // the bit position of the cut point with the given index
+    pub fn bit_position(&self, cut_index: usize, bits_per_sym: usize) -> usize {
+        match self {
+            CutMode::Bit => cut_index,
+            CutMode::Symbol => cut_index * bits_per_sym,
+        }
+    }
+}
+
+// shared by the one and two point crossover stages. pc is the probability
+// of crossing each pair.
+pub struct CrossoverState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub pc: f64,
+    pub bits_used: usize,
+    pub cut_mode: CutMode,
+}
+
+impl<T> CrossoverState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               pc: f64,
+               bits_used: usize,
+               cut_mode: CutMode) -> CrossoverState<T> {
+        CrossoverState { population, pc, bits_used, cut_mode }
     }
 }
 
-pub fn crossover_stage<S, R>(getter: Getter<S, CrossoverState>) -> Stage<S, R>
+pub fn crossover_stage<S, T, R>(getter: Getter<S, CrossoverState<T>>) -> Stage<S, R>
     where R: Rng,
+          T: PrimInt + FromPrimitive + 'static,
           S: 'static {
     let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
-        let mut cross_state = getter(state);
-        let pop_len = cross_state.population.borrow().0.len();
+        let cross_state = getter(state);
+        let words_per_ind = cross_state.population.borrow().0[0].0.len();
         crossover_one_point(&mut cross_state.population.borrow_mut(),
-                            pop_len,
-                            8,
-                            cross_state.pc1,
+                            words_per_ind,
+                            cross_state.bits_used,
+                            cross_state.cut_mode,
+                            cross_state.pc,
+                            rng);
+    });
+
+    f
+}
+
+pub fn crossover_two_point_stage<S, T, R>(getter: Getter<S, CrossoverState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + FromPrimitive + ToPrimitive + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let cross_state = getter(state);
+        let words_per_ind = cross_state.population.borrow().0[0].0.len();
+        crossover_two_point(&mut cross_state.population.borrow_mut(),
+                            words_per_ind,
+                            cross_state.bits_used,
+                            cross_state.cut_mode,
+                            cross_state.pc,
                             rng);
     });
 
     return f;
 }
 
-pub fn crossover_one_point<T, R>(pop: &mut Pop<T>, words_per_ind: usize, bits_per_sym: usize, pc1: f64, rng: &mut R) 
+// One point crossover of neighbouring pairs, cutting where cut_mode allows. Only the
+// used bits of each word are exchanged.
+pub fn crossover_one_point<T, R>(pop: &mut Pop<T>, words_per_ind: usize, bits_per_sym: usize, cut_mode: CutMode, pc1: f64, rng: &mut R) 
     where R: Rng,
           T: PrimInt + FromPrimitive {
     let pc1_sampler = Uniform::new(0.0, 1.0).unwrap();
-    let cross_point_sampler = Uniform::new(0.0, (words_per_ind * bits_per_sym) as f64).unwrap();
+    let cross_point_sampler = Uniform::new(0.0, cut_mode.num_cut_points(words_per_ind, bits_per_sym) as f64).unwrap();
 
-    for mut pair in pop.0.chunks_mut(2) {
+    for pair in pop.0.chunks_mut(2) {
         if pair.len() != 2 {
             break;
         }
 
         if pc1_sampler.sample(rng) < pc1 {
-            let cross_point = cross_point_sampler.sample(rng) as usize;
+            let cross_point = cut_mode.bit_position(cross_point_sampler.sample(rng) as usize, bits_per_sym);
 
-            cross_at_point(&mut pair, bits_per_sym, cross_point);
+            cross_at_sorted_points(pair, bits_per_sym, &[cross_point]);
         }
     }
 }
@@ -72,7 +129,7 @@ pub fn cross_at_point<T: PrimInt + FromPrimitive>(pair: &mut [Ind<T>], bits_per_
     // cross the word that the cross point is within
     let l1 = pair[0].0[cross_word_index];
     let l2 = pair[1].0[cross_word_index];
-    let bit_mask = T::from_u32(2_u32.pow((cross_point % bits_per_sym) as u32) - 1).unwrap();
+    let bit_mask = low_bit_mask::<T>(cross_point % bits_per_sym);
     pair[0].0[cross_word_index] = (l1 & bit_mask) | (l2 & !bit_mask);
     pair[1].0[cross_word_index] = (l2 & bit_mask) | (l1 & !bit_mask);
 }
@@ -88,24 +145,26 @@ fn test_cross_at_point() {
     assert!(pair[1] == Ind(vec!(0xF, 0xF, 0xC, 0x0, 0x0)));
 }
 
-pub fn crossover_two_point<T, R>(pop: &mut Box<Pop<T>>, words_per_ind: usize, bits_per_sym: usize, pc2: f64, rng: &mut R) 
+// Two point crossover of neighbouring pairs, exchanging the used bits between two cut
+// points chosen where cut_mode allows.
+pub fn crossover_two_point<T, R>(pop: &mut Pop<T>, words_per_ind: usize, bits_per_sym: usize, cut_mode: CutMode, pc2: f64, rng: &mut R) 
     where R: Rng,
           T: PrimInt + FromPrimitive + ToPrimitive {
     let pc2_sampler = Uniform::new(0.0, 1.0).unwrap();
-    let cross_point_sampler = Uniform::new(0.0, (words_per_ind * bits_per_sym) as f64).unwrap();
+    let cross_point_sampler = Uniform::new(0.0, cut_mode.num_cut_points(words_per_ind, bits_per_sym) as f64).unwrap();
 
-    for mut pair in pop.0.chunks_mut(2) {
+    for pair in pop.0.chunks_mut(2) {
         if pair.len() != 2 {
             break;
         }
 
         if pc2_sampler.sample(rng) < pc2 {
-            let cross_point_one = cross_point_sampler.sample(rng) as usize;
-            let cross_point_two = cross_point_sampler.sample(rng) as usize;
+            let cross_point_one = cut_mode.bit_position(cross_point_sampler.sample(rng) as usize, bits_per_sym);
+            let cross_point_two = cut_mode.bit_position(cross_point_sampler.sample(rng) as usize, bits_per_sym);
 
             let mut locs = [cross_point_one, cross_point_two];
             locs.sort();
-            cross_at_points(&mut pair, bits_per_sym, &locs);
+            cross_at_sorted_points(pair, bits_per_sym, &locs);
         }
     }
 }
@@ -210,9 +269,262 @@ fn test_cross_at_points() {
     assert!(pair[1] == Ind(vec!(0x0E, 0x0C, 0x00, 0x00, 0x00)));
 }
 
+// crossover with a given number of cut points for each crossed pair
+pub struct NPointCrossoverState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub pc: f64,
+    pub bits_used: usize,
+    pub cut_mode: CutMode,
+    pub num_points: usize,
+}
+
+impl<T> NPointCrossoverState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               pc: f64,
+               bits_used: usize,
+               cut_mode: CutMode,
+               num_points: usize) -> NPointCrossoverState<T> {
+        NPointCrossoverState { population, pc, bits_used, cut_mode, num_points }
+    }
+}
+
+pub fn crossover_n_point_stage<S, T, R>(getter: Getter<S, NPointCrossoverState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let cross_state = getter(state);
+        let words_per_ind = cross_state.population.borrow().0[0].0.len();
+        crossover_n_point(&mut cross_state.population.borrow_mut(),
+                          words_per_ind,
+                          cross_state.bits_used,
+                          cross_state.cut_mode,
+                          cross_state.num_points,
+                          cross_state.pc,
+                          rng);
+    });
+
+    f
+}
+
+// N-point crossover of neighbouring pairs, each crossed with probability pc at num_points
+// distinct cut points within the used bits of the individual, where cut_mode allows.
+pub fn crossover_n_point<T, R>(pop: &mut Pop<T>, words_per_ind: usize, bits_per_sym: usize, cut_mode: CutMode, num_points: usize, pc: f64, rng: &mut R)
+    where R: Rng,
+          T: PrimInt {
+    let num_cut_points = cut_mode.num_cut_points(words_per_ind, bits_per_sym);
+    assert!(num_points <= num_cut_points, "Cannot choose {} cut points from {} places to cut!", num_points, num_cut_points);
+
+    let mut cross_points = Vec::with_capacity(num_points);
+    for pair in pop.0.chunks_mut(2) {
+        if pair.len() != 2 {
+            break;
+        }
+
+        if rng.gen::<f64>() < pc {
+            cross_points.clear();
+            cross_points.extend(rand::seq::index::sample(rng, num_cut_points, num_points)
+                                    .iter()
+                                    .map(|cut_index| cut_mode.bit_position(cut_index, bits_per_sym)));
+            cross_points.sort();
+
+            cross_at_sorted_points(pair, bits_per_sym, &cross_points);
+        }
+    }
+}
+
+// Swap every bit of the pair that comes before an odd number of the sorted cross points,
+// so the segments between cut points alternate between the individuals. Bit positions
+// count bits_per_sym bits in each word, from the least significant bit, so the unused
+// high bits of a word are never exchanged.
+pub fn cross_at_sorted_points<T: PrimInt>(pair: &mut [Ind<T>], bits_per_sym: usize, cross_points: &[usize]) {
+    let used_mask = low_bit_mask::<T>(bits_per_sym);
+    let ind_len = pair[0].0.len().min(pair[1].0.len());
+
+    let mut num_passed = 0;
+    for word_index in 0..ind_len {
+        let word_start = word_index * bits_per_sym;
+        let word_end = word_start + bits_per_sym;
+
+        while num_passed < cross_points.len() && cross_points[num_passed] <= word_start {
+            num_passed += 1;
+        }
+
+        // count every remaining point as covering the whole word, and then correct for
+        // the points within the word, which only cover the bits below them
+        let mut swap_mask = if (cross_points.len() - num_passed) % 2 == 1 { used_mask } else { T::zero() };
+        for point in cross_points[num_passed..].iter().take_while(|point| **point < word_end) {
+            swap_mask = swap_mask ^ (used_mask & !low_bit_mask::<T>(point - word_start));
+        }
+
+        swap_bits(pair, word_index, swap_mask);
+    }
+}
+
+// exchange the bits under mask between the pair's words at word_index
+fn swap_bits<T: PrimInt>(pair: &mut [Ind<T>], word_index: usize, mask: T) {
+    let diff = (pair[0].0[word_index] ^ pair[1].0[word_index]) & mask;
+    pair[0].0[word_index] = pair[0].0[word_index] ^ diff;
+    pair[1].0[word_index] = pair[1].0[word_index] ^ diff;
+}
+
+// uniform crossover, where each bit or symbol is swapped with probability prob_swap
+pub struct UniformCrossoverState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub pc: f64,
+    pub bits_used: usize,
+    pub prob_swap: f64,
+}
+
+impl<T> UniformCrossoverState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               pc: f64,
+               bits_used: usize,
+               prob_swap: f64) -> UniformCrossoverState<T> {
+        UniformCrossoverState { population, pc, bits_used, prob_swap }
+    }
+}
+
+pub fn crossover_uniform_bits_stage<S, T, R>(getter: Getter<S, UniformCrossoverState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let cross_state = getter(state);
+        crossover_uniform_bits(&mut cross_state.population.borrow_mut(),
+                               cross_state.bits_used,
+                               cross_state.prob_swap,
+                               cross_state.pc,
+                               rng);
+    });
+
+    f
+}
+
+pub fn crossover_uniform_symbols_stage<S, T, R>(getter: Getter<S, UniformCrossoverState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let cross_state = getter(state);
+        crossover_uniform_symbols(&mut cross_state.population.borrow_mut(),
+                                  cross_state.bits_used,
+                                  cross_state.prob_swap,
+                                  cross_state.pc,
+                                  rng);
+    });
+
+    f
+}
+
+// Bit-level uniform crossover of neighbouring pairs. Each pair is crossed with probability
+// pc, and then each used bit is swapped with probability prob_swap.
+pub fn crossover_uniform_bits<T, R>(pop: &mut Pop<T>, bits_per_sym: usize, prob_swap: f64, pc: f64, rng: &mut R)
+    where R: Rng,
+          T: PrimInt {
+    for pair in pop.0.chunks_mut(2) {
+        if pair.len() != 2 {
+            break;
+        }
+
+        if rng.gen::<f64>() < pc {
+            let ind_len = pair[0].0.len().min(pair[1].0.len());
+            for word_index in 0..ind_len {
+                let mut swap_mask = T::zero();
+                for bit_index in 0..bits_per_sym {
+                    if rng.gen::<f64>() < prob_swap {
+                        swap_mask = swap_mask | (T::one() << bit_index);
+                    }
+                }
+
+                swap_bits(pair, word_index, swap_mask);
+            }
+        }
+    }
+}
+
+// Symbol-level uniform crossover of neighbouring pairs. Each pair is crossed with probability
+// pc, and then each symbol is swapped whole with probability prob_swap.
+pub fn crossover_uniform_symbols<T, R>(pop: &mut Pop<T>, bits_per_sym: usize, prob_swap: f64, pc: f64, rng: &mut R)
+    where R: Rng,
+          T: PrimInt {
+    let used_mask = low_bit_mask::<T>(bits_per_sym);
+
+    for pair in pop.0.chunks_mut(2) {
+        if pair.len() != 2 {
+            break;
+        }
+
+        if rng.gen::<f64>() < pc {
+            let ind_len = pair[0].0.len().min(pair[1].0.len());
+            for word_index in 0..ind_len {
+                if rng.gen::<f64>() < prob_swap {
+                    swap_bits(pair, word_index, used_mask);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_n_point_and_uniform_crossover() {
+    // the high nibble is unused, and must never be exchanged
+    let ones = Ind(vec!(0xFFu8, 0xFF, 0xFF));
+    let zeros = Ind(vec!(0x00u8, 0x00, 0x00));
+
+    let mut pair = [zeros.clone(), ones.clone()];
+    cross_at_sorted_points(&mut pair, 4, &[2, 5, 10]);
+    assert_eq!(pair[0], Ind(vec!(0x03, 0x0E, 0x03)));
+    assert_eq!(pair[1], Ind(vec!(0xFC, 0xF1, 0xFC)));
+
+    let mut rng = R::seed_from_u64(0);
+
+    let mut pop = Pop(vec!(zeros.clone(), ones.clone()));
+    crossover_n_point(&mut pop, 3, 4, CutMode::Bit, 3, 1.0, &mut rng);
+    assert!(pop.0[0].0.iter().all(|word| word & 0xF0 == 0x00));
+    assert!(pop.0[1].0.iter().all(|word| word & 0xF0 == 0xF0));
+    assert_eq!(pop.0[0].0.iter().zip(pop.0[1].0.iter()).map(|(a, b)| a ^ b).collect::<Vec<u8>>(), vec!(0xFF, 0xFF, 0xFF));
+
+    let mut pop = Pop(vec!(zeros.clone(), ones.clone()));
+    crossover_uniform_bits(&mut pop, 4, 1.0, 1.0, &mut rng);
+    assert_eq!(pop, Pop(vec!(Ind(vec!(0x0F, 0x0F, 0x0F)), Ind(vec!(0xF0, 0xF0, 0xF0)))));
+
+    let mut pop = Pop(vec!(zeros.clone(), ones.clone()));
+    crossover_uniform_symbols(&mut pop, 4, 0.5, 1.0, &mut rng);
+    assert!(pop.0[0].0.iter().all(|word| *word == 0x00 || *word == 0x0F));
+    assert!(pop.0[1].0.iter().all(|word| *word == 0xFF || *word == 0xF0));
+}
+
+#[test]
+fn test_cut_modes() {
+    let ones = Ind(vec!(0xFFu8, 0xFF, 0xFF, 0xFF));
+    let zeros = Ind(vec!(0x00u8, 0x00, 0x00, 0x00));
+
+    assert_eq!(CutMode::Bit.num_cut_points(4, 3), 12);
+    assert_eq!(CutMode::Symbol.num_cut_points(4, 3), 4);
+    assert_eq!(CutMode::Symbol.bit_position(2, 3), 6);
+
+    let mut rng = R::seed_from_u64(0);
+    for cut_mode in [CutMode::Bit, CutMode::Symbol] {
+        for _ in 0..20 {
+            let mut pop = Pop(vec!(zeros.clone(), ones.clone()));
+            crossover_one_point(&mut pop, 4, 3, cut_mode, 1.0, &mut rng);
+            crossover_two_point(&mut pop, 4, 3, cut_mode, 1.0, &mut rng);
+
+            // the unused bits stay with their individual
+            assert!(pop.0[0].0.iter().all(|word| word & 0xF8 == 0x00));
+            assert!(pop.0[1].0.iter().all(|word| word & 0xF8 == 0xF8));
+
+            if cut_mode == CutMode::Symbol {
+                assert!(pop.0[0].0.iter().all(|word| *word == 0x00 || *word == 0x07));
+            }
+        }
+    }
+}
+
 pub fn cross_word<T>(first: T, second: T, bit_index: u8) -> (T, T) 
     where T: PrimInt + FromPrimitive + ToPrimitive {
-    let bit_mask = T::from_u32(2_u32.pow(bit_index.to_u32().unwrap()) - 1).unwrap();
+    let bit_mask = low_bit_mask::<T>(bit_index.to_usize().unwrap());
 
     let first_result  = (first  & !bit_mask) | (second & bit_mask);
     let second_result = (second & !bit_mask) | (first  & bit_mask);
@@ -220,6 +532,24 @@ pub fn cross_word<T>(first: T, second: T, bit_index: u8) -> (T, T)
     (first_result, second_result)
 }
 
+// mask with the lowest num_bits bits set. This avoids overflowing when
+// num_bits is the full width of the word.
+pub fn low_bit_mask<T: PrimInt>(num_bits: usize) -> T {
+    if num_bits >= T::zero().count_zeros() as usize {
+        !T::zero()
+    } else {
+        !(!T::zero() << num_bits)
+    }
+}
+
+#[test]
+fn test_low_bit_mask() {
+    assert_eq!(low_bit_mask::<u8>(0), 0x00);
+    assert_eq!(low_bit_mask::<u8>(4), 0x0F);
+    assert_eq!(low_bit_mask::<u8>(8), 0xFF);
+    assert_eq!(low_bit_mask::<u64>(64), u64::MAX);
+}
+
 #[test]
 fn test_cross_word() {
     let (first, second) = cross_word(0xff, 0x00, 4);
@@ -13,7 +13,7 @@ pub fn fittest(fitnesses: &Vec<f64>) -> usize {
     let (index, _fitness) = 
         fitnesses.iter()
                   .enumerate()
-                  .fold((0, 0.0), |(best_index, best_fitness), (index, fitness)| {
+                  .fold((0, std::f64::NEG_INFINITY), |(best_index, best_fitness), (index, fitness)| {
                      if *fitness > best_fitness {
                          (index, *fitness)
                      } else {
@@ -23,16 +23,27 @@ pub fn fittest(fitnesses: &Vec<f64>) -> usize {
     index
 }
 
-pub struct EvalState<R> {
-    pub population: Rc<RefCell<PopU8>>,
-    pub eval: Eval<Ind<u8>, R>,
+// Seed for the random number stream handed to the evaluation of the individual at
+// index. Each individual gets its own stream, so an evaluation's randomness does
+// not depend on how many random numbers earlier evaluations used.
+pub fn eval_seed(seed: u64, index: usize) -> u64 {
+    // splitmix64 finalizer
+    let mut z = seed.wrapping_add((index as u64).wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub struct EvalState<T, R> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub eval: Eval<Ind<T>, R>,
     pub fitnesses: Rc<RefCell<Vec<f64>>>,
 }
 
-impl<R> EvalState<R> {
-    pub fn new(population: Rc<RefCell<PopU8>>,
-               eval: Eval<Ind<u8>, R>,
-               fitnesses: Rc<RefCell<Vec<f64>>>) -> EvalState<R> {
+impl<T, R> EvalState<T, R> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               eval: Eval<Ind<T>, R>,
+               fitnesses: Rc<RefCell<Vec<f64>>>) -> EvalState<T, R> {
         return EvalState {
             population,
             eval,
@@ -41,12 +52,13 @@ impl<R> EvalState<R> {
     }
 }
 
-pub fn evaluate_stage<S, R>(getter: Getter<S, EvalState<R>>) -> Stage<S, R>
-    where R: Rng + 'static,
+pub fn evaluate_stage<S, T, R>(getter: Getter<S, EvalState<T, R>>) -> Stage<S, R>
+    where R: Rng + SeedableRng + 'static,
+          T: 'static,
           S: 'static {
     let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
-        let mut eval_state = getter(state);
-        evaluate(&mut eval_state.population.borrow_mut(),
+        let eval_state = getter(state);
+        evaluate(&eval_state.population.borrow(),
                  eval_state.eval.clone(),
                  &mut eval_state.fitnesses.borrow_mut(),
                  rng);
@@ -55,13 +67,15 @@ pub fn evaluate_stage<S, R>(getter: Getter<S, EvalState<R>>) -> Stage<S, R>
     return f;
 }
 
-pub fn evaluate<R>(pop: &PopU8,
-                   eval: Eval<IndU8, R>,
-                   fitnesses: &mut Vec<f64>,
-                   rng: &mut R)
-    where R: Rng {
+pub fn evaluate<T, R>(pop: &Pop<T>,
+                      eval: Eval<Ind<T>, R>,
+                      fitnesses: &mut [f64],
+                      rng: &mut R)
+    where R: Rng + SeedableRng {
+    let gen_seed: u64 = rng.gen();
     for (index, ind) in pop.0.iter().enumerate() {
-        fitnesses[index] = eval(ind, rng);
+        let mut eval_rng = R::seed_from_u64(eval_seed(gen_seed, index));
+        fitnesses[index] = eval(ind, &mut eval_rng);
     }
 }
 
@@ -6,27 +6,67 @@ use std::boxed::Box;
 
 use rand::prelude::*;
 
-use num::PrimInt;
+use num::{PrimInt, FromPrimitive};
 
 use crate::types::*;
 use crate::crossover::*;
 use crate::point_mutation::*;
 use crate::selection::*;
 use crate::evaluation::*;
+use crate::termination::*;
+use crate::observer::*;
+use crate::scaling::*;
+use crate::hall_of_fame::*;
+use crate::niching::*;
+use crate::replacement::*;
+use crate::mating::*;
 use crate::stage::Stage;
 
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct GaParams {
     pub ind_size: usize,
     pub pop_size: usize,
 
+    // number of bits used within each gene, starting from the least significant bit
+    pub bits_used: usize,
+
     pub num_gens: usize,
 
     pub elitism: usize,
 
     pub prob_pm: f64,
     pub prob_pc1: f64,
+
+    // whether crossover cuts between any bits or only between whole genes
+    pub cut_mode: CutMode,
+
+    pub selection: Selection,
+
+    // the number of distinct best individuals kept over the run, and how many of them
+    // are copied unchanged into each generation, after variation and before evaluation.
+    // Only generational runs reinsert elites, and other survival schemes reject them.
+    pub hall_of_fame_size: usize,
+    pub hall_of_fame_elites: usize,
+
+    pub objective: Objective,
+    pub scaling: FitnessScaling,
+    pub niching: Niching,
+
+    // how selected individuals are paired for crossover
+    pub mating: Mating,
+
+    pub termination: Termination,
+
+    // generational replacement, or (mu, lambda) or (mu + lambda) survival
+    pub survival: Survival,
+
+    // offspring created per step in steady-state mode, and how they enter the population
+    pub num_offspring: usize,
+    pub replacement: ReplacementPolicy,
+
+    // all randomness in a run is derived from this seed
+    pub seed: u64,
 }
 
 impl Default for GaParams {
@@ -34,29 +74,47 @@ impl Default for GaParams {
         GaParams {
             ind_size: 100,
             pop_size: 100,
+            bits_used: 8,
             num_gens: 1000,
             elitism: 0,
             prob_pm: 0.01,
             prob_pc1: 0.6,
+            cut_mode: CutMode::default(),
+            selection: Selection::default(),
+            hall_of_fame_size: 10,
+            hall_of_fame_elites: 0,
+            objective: Objective::default(),
+            scaling: FitnessScaling::default(),
+            niching: Niching::default(),
+            mating: Mating::default(),
+            termination: Termination::default(),
+            survival: Survival::default(),
+            num_offspring: 2,
+            replacement: ReplacementPolicy::default(),
+            seed: 0,
         }
     }
 }
 
 #[derive(Clone)]
-pub struct GaState<R> {
+pub struct GaState<T, R> {
     params: GaParams,
-    population: Rc<RefCell<PopU8>>,
-    alt_population: Rc<RefCell<PopU8>>,
-    eval: Eval<IndU8, R>,
+    population: Rc<RefCell<Pop<T>>>,
+    alt_population: Rc<RefCell<Pop<T>>>,
+    eval: Eval<Ind<T>, R>,
     fitnesses: Rc<RefCell<Vec<f64>>>,
 }
 
-pub fn populationU8<R: Rng>(pop_size: usize, ind_size: usize, rng: &mut R) -> PopU8 {
+pub fn population<T, R>(pop_size: usize, ind_size: usize, bits_used: usize, rng: &mut R) -> Pop<T>
+    where T: PrimInt + FromPrimitive,
+          R: Rng {
+    let bit_mask: u64 = low_bit_mask(bits_used);
+
     let mut pop = Vec::with_capacity(pop_size);
     for _ in 0..pop_size {
         let mut ind_vec = Vec::with_capacity(ind_size);
         for _ in 0..ind_size {
-            ind_vec.push(rng.gen_range(0, 0xFF) as u8);
+            ind_vec.push(T::from_u64(rng.gen::<u64>() & bit_mask).unwrap());
         }
         pop.push(Ind(ind_vec));
     }
@@ -66,57 +124,498 @@ pub fn populationU8<R: Rng>(pop_size: usize, ind_size: usize, rng: &mut R) -> Po
     return population;
 }
 
-pub fn populationU8Zeros(pop_size: usize, ind_size: usize) -> PopU8 {
-    let ind = Ind(std::iter::repeat(0x0).take(ind_size).collect());
+pub fn population_zeros<T: PrimInt>(pop_size: usize, ind_size: usize) -> Pop<T> {
+    let ind = Ind(std::iter::repeat(T::zero()).take(ind_size).collect());
     let population = Pop(iter::repeat(ind).take(pop_size).collect());
 
     return population;
 }
 
-impl<R: Rng> GaState<R> {
-    pub fn create_ga(params: &GaParams, eval: Eval<IndU8, R>, rng: &mut R) -> GaState<R> {
-        let population = populationU8(params.pop_size, params.ind_size, rng);
-        let alt_population = populationU8Zeros(params.pop_size, params.ind_size);
+pub fn populationU8<R: Rng>(pop_size: usize, ind_size: usize, rng: &mut R) -> PopU8 {
+    population(pop_size, ind_size, 8, rng)
+}
+
+pub fn populationU8Zeros(pop_size: usize, ind_size: usize) -> PopU8 {
+    population_zeros(pop_size, ind_size)
+}
+
+impl<T, R> GaState<T, R>
+    where T: PrimInt + FromPrimitive,
+          R: Rng {
+    pub fn create_ga(params: &GaParams, eval: Eval<Ind<T>, R>, rng: &mut R) -> GaState<T, R> {
+        let gene_bits = T::zero().count_zeros() as usize;
+        assert!(params.bits_used > 0 && params.bits_used <= gene_bits,
+                "bits_used was {}, but genes only have {} bits", params.bits_used, gene_bits);
+
+        let population = population(params.pop_size, params.ind_size, params.bits_used, rng);
+        let alt_population = population_zeros(params.pop_size, params.ind_size);
         let fitnesses = vec![0.0; params.pop_size];
 
         return GaState { population: Rc::new(RefCell::new(population)),
                          alt_population: Rc::new(RefCell::new(alt_population)),
                          eval,
-                         params: *params,
+                         params: params.clone(),
                          fitnesses: Rc::new(RefCell::new(fitnesses)),
         };
     }
 }
 
-pub fn ga<R>(params: &GaParams,
-             eval: Eval<IndU8, R>,
-             rng: &mut R) -> Rc<RefCell<PopU8>> 
-    where R: Rng + 'static {
+#[derive(Clone, Debug)]
+pub struct GaResult<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+
+    pub best: Ind<T>,
+    pub best_fitness: f64,
+
+    // best fitness found in each generation
+    pub fitness_history: Vec<f64>,
+
+    pub stop_reason: StopReason,
+
+    // the best distinct individuals found over the whole run
+    pub hall_of_fame: HallOfFame<T>,
+}
+
+pub fn ga<T, R>(params: &GaParams,
+                eval: Eval<Ind<T>, R>) -> GaResult<T> 
+    where T: PrimInt + FromPrimitive + 'static,
+          R: Rng + SeedableRng + 'static {
+    ga_with_observer(params, eval, &mut NullObserver)
+}
+
+pub fn ga_with_observer<T, R>(params: &GaParams,
+                              eval: Eval<Ind<T>, R>,
+                              observer: &mut dyn Observer<T>) -> GaResult<T> 
+    where T: PrimInt + FromPrimitive + 'static,
+          R: Rng + SeedableRng + 'static {
+    if params.survival != Survival::Generational {
+        return ga_es_with_observer(params, eval, observer);
+    }
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
     let state = GaState::create_ga(&params, eval, rng);
 
-    let pm_stage: Stage<GaState<R>, R> = point_mutation_stage(Rc::new(|state: &GaState<R>| {
-        return PmState::new(state.population.clone(), state.params.prob_pm, 8);
+    let pm_stage: Stage<GaState<T, R>, R> = point_mutation_stage(Rc::new(|state: &GaState<T, R>| {
+        PmState::new(state.population.clone(), state.params.prob_pm, state.params.bits_used)
     }));
 
-    let cross_stage: Stage<GaState<R>, R> = crossover_stage(Rc::new(|state: &GaState<R>| {
-        return CrossoverState::new(state.population.clone(), state.params.prob_pc1);
+    let cross_stage: Stage<GaState<T, R>, R> = crossover_stage(Rc::new(|state: &GaState<T, R>| {
+        CrossoverState::new(state.population.clone(), state.params.prob_pc1, state.params.bits_used, state.params.cut_mode)
     }));
 
-    let eval_stage: Stage<GaState<R>, R> = evaluate_stage(Rc::new(|state: &GaState<R>| {
+    let cross_stage: Stage<GaState<T, R>, R> = mating_stage(Rc::new(|state: &GaState<T, R>| {
+        MatingState::new(state.population.clone(), state.fitnesses.clone(), state.params.mating, state.params.bits_used)
+    }), cross_stage);
+
+    let eval_stage: Stage<GaState<T, R>, R> = evaluate_stage(Rc::new(|state: &GaState<T, R>| {
         return EvalState::new(state.population.clone(), state.eval.clone(), state.fitnesses.clone());
     }));
 
-    let sus_stage : Stage<GaState<R>, R> = sus_stage(Rc::new(|state: &GaState<R>| {
-        return SusState::new(state.population.clone(), state.alt_population.clone(), state.fitnesses.clone(), state.params.elitism);
+    let share_stage: Stage<GaState<T, R>, R> = sharing_stage(Rc::new(|state: &GaState<T, R>| {
+        SharingState::new(state.population.clone(), state.fitnesses.clone(), state.params.niching, state.params.bits_used)
+    }));
+
+    let scale_stage: Stage<GaState<T, R>, R> = scaling_stage(Rc::new(|state: &GaState<T, R>| {
+        ScalingState::new(state.fitnesses.clone(), state.params.scaling)
     }));
 
-    for _ in 0..params.num_gens {
+    // the fitnesses are left aligned with the selected individuals, for mating
+    let select_stage: Stage<GaState<T, R>, R> = selection_stage(Rc::new(|state: &GaState<T, R>| {
+        SelectionState::new(state.population.clone(), state.alt_population.clone(), state.fitnesses.clone(), state.params.selection, state.params.elitism)
+    }));
+
+    let mut best = state.population.borrow().0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
+
+    let mut progress = Progress::new();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
         pm_stage(&state, rng);
         cross_stage(&state, rng);
+        hall_of_fame.reinsert(&mut state.population.borrow_mut(), params.hall_of_fame_elites);
         eval_stage(&state, rng);
-        sus_stage(&state, rng);
+
+        let control;
+        {
+            let pop = state.population.borrow();
+            let mut fitnesses = state.fitnesses.borrow_mut();
+            params.objective.convert(&mut fitnesses);
+
+            let generation = progress.generation;
+            let index_fittest = fittest(&fitnesses);
+            fitness_history.push(fitnesses[index_fittest]);
+            let improved = progress.update(fitnesses[index_fittest], fitnesses.len());
+            hall_of_fame.update(&pop, &fitnesses, generation);
+
+            control = observer.on_generation(generation, &pop, &fitnesses);
+            if improved {
+                best = pop.0[index_fittest].clone();
+                observer.on_new_best(generation, &best, progress.best_fitness);
+            }
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+
+        share_stage(&state, rng);
+        scale_stage(&state, rng);
+        select_stage(&state, rng);
     }
 
-    return state.population;
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    GaResult {
+        population: state.population,
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        hall_of_fame,
+    }
 }
 
+// The GA with (mu, lambda) or (mu + lambda) survival, with mu = params.pop_size. Each
+// generation selects lambda parents, applies point mutation and crossover to copies of
+// them, and keeps the survivors given by params.survival.
+pub fn ga_es_with_observer<T, R>(params: &GaParams,
+                                 eval: Eval<Ind<T>, R>,
+                                 observer: &mut dyn Observer<T>) -> GaResult<T>
+    where T: PrimInt + FromPrimitive + 'static,
+          R: Rng + SeedableRng + 'static {
+    assert!(params.hall_of_fame_elites == 0, "Hall of fame elites are only reinserted by generational runs, not (mu, lambda) or (mu + lambda) runs!");
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
+    let state = GaState::create_ga(params, eval.clone(), rng);
+    let mut pop = state.population.borrow().clone();
+
+    let num_offspring = params.survival.num_offspring(params.pop_size);
+    let mut offspring = population_zeros(num_offspring, params.ind_size);
+
+    let mut fitnesses = vec![0.0; params.pop_size];
+    let mut offspring_fitnesses = vec![0.0; num_offspring];
+    evaluate(&pop, eval.clone(), &mut fitnesses, rng);
+    params.objective.convert(&mut fitnesses);
+
+    let mut best = pop.0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
+    hall_of_fame.update(&pop, &fitnesses, 0);
+
+    let mut progress = Progress::new();
+    progress.evaluations += fitnesses.len();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        let mut selection_fitnesses = fitnesses.clone();
+        params.niching.apply(&pop, params.bits_used, &mut selection_fitnesses);
+        params.scaling.apply(&mut selection_fitnesses);
+        let selected = select_indices(params.selection, &selection_fitnesses, num_offspring, params.elitism, rng);
+        copy_selected(&pop, &mut offspring, &selected);
+
+        point_mutation(&mut offspring, params.bits_used, params.prob_pm, rng);
+        let mut parent_fitnesses = selected_fitnesses(&fitnesses, &selected);
+        mate(params.mating, &mut offspring, &mut parent_fitnesses, params.bits_used, &mut |offspring: &mut Pop<T>, rng: &mut R| {
+            crossover_one_point(offspring, params.ind_size, params.bits_used, params.cut_mode, params.prob_pc1, rng);
+        }, rng);
+
+        evaluate(&offspring, eval.clone(), &mut offspring_fitnesses, rng);
+        params.objective.convert(&mut offspring_fitnesses);
+
+        let generation = progress.generation;
+        hall_of_fame.update(&offspring, &offspring_fitnesses, generation);
+
+        survive(params.survival, &mut pop, &mut fitnesses, &offspring, &offspring_fitnesses);
+
+        let index_fittest = fittest(&fitnesses);
+        fitness_history.push(fitnesses[index_fittest]);
+        let improved = progress.update(fitnesses[index_fittest], offspring_fitnesses.len());
+
+        let control = observer.on_generation(generation, &pop, &fitnesses);
+        if improved {
+            best = pop.0[index_fittest].clone();
+            observer.on_new_best(generation, &best, progress.best_fitness);
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    *state.population.borrow_mut() = pop;
+    *state.fitnesses.borrow_mut() = fitnesses;
+
+    GaResult {
+        population: state.population,
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        hall_of_fame,
+    }
+}
+
+// Steady-state GA. Each step chooses params.num_offspring parents by binary
+// tournament, applies point mutation and crossover to copies of them, evaluates only
+// those offspring, and places them into the population using params.replacement.
+// Each step counts as one generation for termination and the fitness history.
+pub fn ga_steady_state<T, R>(params: &GaParams,
+                             eval: Eval<Ind<T>, R>) -> GaResult<T>
+    where T: PrimInt + FromPrimitive + 'static,
+          R: Rng + SeedableRng + 'static {
+    ga_steady_state_with_observer(params, eval, &mut NullObserver)
+}
+
+pub fn ga_steady_state_with_observer<T, R>(params: &GaParams,
+                                           eval: Eval<Ind<T>, R>,
+                                           observer: &mut dyn Observer<T>) -> GaResult<T>
+    where T: PrimInt + FromPrimitive + 'static,
+          R: Rng + SeedableRng + 'static {
+    assert!(params.hall_of_fame_elites == 0, "Hall of fame elites are only reinserted by generational runs, not steady-state runs!");
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
+    let state = GaState::create_ga(params, eval.clone(), rng);
+    let mut pop = state.population.borrow().clone();
+
+    let mut fitnesses = vec![0.0; params.pop_size];
+    evaluate(&pop, eval.clone(), &mut fitnesses, rng);
+    params.objective.convert(&mut fitnesses);
+
+    let mut offspring = Pop(Vec::with_capacity(params.num_offspring));
+    let mut offspring_fitnesses = vec![0.0; params.num_offspring];
+    let mut parents = Vec::with_capacity(params.num_offspring);
+    let replacement = ReplacementParams { policy: params.replacement, elitism: params.elitism, bits_used: params.bits_used };
+
+    let mut best = pop.0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
+
+    let mut progress = Progress::new();
+    progress.evaluations += fitnesses.len();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        offspring.0.clear();
+        parents.clear();
+        for _ in 0..params.num_offspring {
+            let parent_index = tournament_index(&fitnesses, 2, rng);
+            offspring.0.push(pop.0[parent_index].clone());
+            parents.push(parent_index);
+        }
+
+        // mating may rearrange the offspring, so their parents are rearranged to match
+        point_mutation(&mut offspring, params.bits_used, params.prob_pm, rng);
+        let mut parent_fitnesses = selected_fitnesses(&fitnesses, &parents);
+        let order = mate(params.mating, &mut offspring, &mut parent_fitnesses, params.bits_used, &mut |offspring: &mut Pop<T>, rng: &mut R| {
+            crossover_one_point(offspring, params.ind_size, params.bits_used, params.cut_mode, params.prob_pc1, rng);
+        }, rng);
+        parents = order.iter().map(|index| parents[*index]).collect();
+
+        evaluate(&offspring, eval.clone(), &mut offspring_fitnesses, rng);
+        params.objective.convert(&mut offspring_fitnesses);
+        hall_of_fame.update(&offspring, &offspring_fitnesses, progress.generation);
+
+        replace_offspring(replacement, &mut pop, &mut fitnesses, &mut offspring, &offspring_fitnesses, &parents, rng);
+
+        let generation = progress.generation;
+        let index_fittest = fittest(&fitnesses);
+        fitness_history.push(fitnesses[index_fittest]);
+        let improved = progress.update(fitnesses[index_fittest], offspring_fitnesses.len());
+
+        let control = observer.on_generation(generation, &pop, &fitnesses);
+        if improved {
+            best = pop.0[index_fittest].clone();
+            observer.on_new_best(generation, &best, progress.best_fitness);
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    *state.population.borrow_mut() = pop;
+    *state.fitnesses.borrow_mut() = fitnesses;
+
+    GaResult {
+        population: state.population,
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        hall_of_fame,
+    }
+}
+
+#[test]
+fn test_ga_respects_bits_used() {
+    let params = GaParams {
+        ind_size: 20,
+        pop_size: 20,
+        bits_used: 12,
+        num_gens: 20,
+        ..GaParams::default()
+    };
+
+    let eval: Eval<Ind<u16>, R> =
+        Rc::new(|ind, _rng| {
+            return 1.0 + ind.0.iter().map(|gene| gene.count_ones() as f64).sum::<f64>();
+        });
+
+    let result = ga(&params, eval);
+
+    for ind in result.population.borrow().0.iter() {
+        assert_eq!(ind.0.len(), params.ind_size);
+        assert!(ind.0.iter().all(|gene| *gene < (1 << params.bits_used)), "{:?}", ind);
+    }
+}
+
+#[test]
+fn test_ga_stops_at_target_fitness() {
+    let mut params = GaParams::default();
+    params.termination = Termination::new().with(Criterion::TargetFitness(1.0));
+
+    let eval: Eval<IndU8, R> = Rc::new(|_ind, _rng| 1.0);
+
+    let result = ga(&params, eval);
+
+    assert_eq!(result.stop_reason, StopReason::TargetFitness);
+    assert_eq!(result.fitness_history.len(), 1);
+}
+
+#[test]
+fn test_ga_tournament_selection() {
+    let params = GaParams {
+        ind_size: 16,
+        pop_size: 20,
+        num_gens: 30,
+        selection: Selection::Tournament { size: 2, prob: 0.9 },
+        ..GaParams::default()
+    };
+
+    let eval: Eval<IndU8, R> =
+        Rc::new(|ind, _rng| {
+            return ind.0.iter().map(|gene| gene.count_ones() as f64).sum::<f64>();
+        });
+
+    let result = ga(&params, eval);
+
+    assert_eq!(result.population.borrow().0.len(), params.pop_size);
+    assert!(result.fitness_history[params.num_gens - 1] >= result.fitness_history[0]);
+}
+
+#[test]
+fn test_ga_comma_survival() {
+    let params = GaParams {
+        ind_size: 16,
+        pop_size: 10,
+        num_gens: 30,
+        survival: Survival::Comma { lambda: 40 },
+        ..GaParams::default()
+    };
+
+    let eval: Eval<IndU8, R> =
+        Rc::new(|ind, _rng| {
+            return ind.0.iter().map(|gene| gene.count_ones() as f64).sum::<f64>();
+        });
+
+    let result = ga(&params, eval);
+
+    assert_eq!(result.population.borrow().0.len(), params.pop_size);
+    assert_eq!(result.fitness_history.len(), params.num_gens);
+    assert!(result.fitness_history[params.num_gens - 1] > result.fitness_history[0]);
+}
+
+#[test]
+fn test_ga_hall_of_fame_elites() {
+    use crate::test_support::*;
+
+    let params = GaParams {
+        ind_size: 10,
+        pop_size: 20,
+        bits_used: 4,
+        num_gens: 20,
+        prob_pm: 0.2,
+        hall_of_fame_elites: 1,
+        ..GaParams::default()
+    };
+
+    let eval: Eval<Ind<u8>, R> =
+        Rc::new(|ind, _rng| {
+            return ind.0.iter().map(|gene| *gene as f64).sum::<f64>();
+        });
+
+    let mut observer = ReinsertionObserver { hall_of_fame: HallOfFame::new(1) };
+    let result = ga_with_observer(&params, eval, &mut observer);
+    assert_eq!(observer.hall_of_fame.best(), result.hall_of_fame.best());
+}
+
+#[test]
+fn test_ga_steady_state_crowding() {
+    let params = GaParams {
+        ind_size: 16,
+        pop_size: 20,
+        num_offspring: 2,
+        replacement: ReplacementPolicy::InverseTournament(3),
+        termination: Termination::new().with(Criterion::MaxEvaluations(20 + 2 * 15)),
+        ..GaParams::default()
+    };
+
+    let eval: Eval<IndU8, R> =
+        Rc::new(|ind, _rng| {
+            return ind.0.iter().map(|gene| gene.count_ones() as f64).sum::<f64>();
+        });
+
+    let result = ga_steady_state(&params, eval.clone());
+
+    assert_eq!(result.stop_reason, StopReason::MaxEvaluations);
+    assert_eq!(result.fitness_history.len(), 15);
+
+    // crowding never replaces an individual with a less fit one, so the best never gets worse
+    for replacement in vec!(ReplacementPolicy::DeterministicCrowding, ReplacementPolicy::RestrictedTournament(4)) {
+        let params = GaParams {
+            replacement,
+            termination: Termination::default(),
+            num_gens: 50,
+            ..params.clone()
+        };
+        let result = ga_steady_state(&params, eval.clone());
+        assert_eq!(result.population.borrow().0.len(), params.pop_size);
+        assert!(result.fitness_history.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
@@ -0,0 +1,125 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use rand::prelude::*;
+
+use types::*;
+use stage::*;
+
+
+// an individual kept by a HallOfFame, with its fitness and the generation it was found in
+#[derive(Clone, Debug, PartialEq)]
+pub struct Famer<T> {
+    pub ind: Ind<T>,
+    pub fitness: f64,
+    pub generation: usize,
+}
+
+// An archive of the best distinct individuals seen over a whole run, fittest first.
+// Unlike elitism, which only keeps indices into the current generation, members stay
+// in the archive until capacity fitter individuals have been found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HallOfFame<T> {
+    pub capacity: usize,
+    pub members: Vec<Famer<T>>,
+}
+
+impl<T: Clone + PartialEq> HallOfFame<T> {
+    pub fn new(capacity: usize) -> HallOfFame<T> {
+        HallOfFame {
+            capacity,
+            members: Vec::with_capacity(capacity + 1),
+        }
+    }
+
+    pub fn best(&self) -> Option<&Famer<T>> {
+        self.members.first()
+    }
+
+    fn admits(&self, fitness: f64) -> bool {
+        self.members.len() < self.capacity ||
+        self.members.last().map_or(false, |worst| fitness > worst.fitness)
+    }
+
+    // Add any individuals of the population fit enough for the archive. An individual
+    // already in the archive keeps the fitness and generation it was first found with.
+    pub fn update(&mut self, pop: &Pop<T>, fitnesses: &[f64], generation: usize) {
+        for (ind, fitness) in pop.0.iter().zip(fitnesses.iter()) {
+            if !self.admits(*fitness) || self.members.iter().any(|member| member.ind == *ind) {
+                continue;
+            }
+
+            let position = self.members.iter()
+                                       .position(|member| *fitness > member.fitness)
+                                       .unwrap_or(self.members.len());
+            self.members.insert(position, Famer { ind: ind.clone(), fitness: *fitness, generation });
+            self.members.truncate(self.capacity);
+        }
+    }
+
+    // Copy the num_elites fittest members over the last individuals of the population, and
+    // return the indices they were copied to, in the order of the members. This is done
+    // after variation, so that the members are evaluated unchanged, and selection places its
+    // own elites at the start, so these do not overwrite them.
+    pub fn reinsert(&self, pop: &mut Pop<T>, num_elites: usize) -> Vec<usize> {
+        let num_inds = pop.0.len();
+        let mut indices = Vec::with_capacity(num_elites);
+        for (offset, (member, ind)) in self.members.iter().take(num_elites).zip(pop.0.iter_mut().rev()).enumerate() {
+            ind.0.clear();
+            ind.0.extend(member.ind.0.iter().cloned());
+            indices.push(num_inds - 1 - offset);
+        }
+
+        indices
+    }
+}
+
+pub struct HallOfFameState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub hall_of_fame: Rc<RefCell<HallOfFame<T>>>,
+    pub num_elites: usize,
+}
+
+impl<T> HallOfFameState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               hall_of_fame: Rc<RefCell<HallOfFame<T>>>,
+               num_elites: usize) -> HallOfFameState<T> {
+        HallOfFameState { population, hall_of_fame, num_elites }
+    }
+}
+
+// reinsert the archive's elites, to be run after variation and before evaluation
+pub fn reinsert_stage<S, T, R>(getter: Getter<S, HallOfFameState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: Clone + PartialEq + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, _rng| {
+        let hall_of_fame_state = getter(state);
+        hall_of_fame_state.hall_of_fame.borrow().reinsert(&mut hall_of_fame_state.population.borrow_mut(),
+                                                          hall_of_fame_state.num_elites);
+    });
+
+    f
+}
+
+#[test]
+fn test_hall_of_fame() {
+    let mut hall_of_fame = HallOfFame::new(2);
+
+    let pop = Pop(vec!(Ind(vec!(0)), Ind(vec!(1)), Ind(vec!(1)), Ind(vec!(2))));
+    hall_of_fame.update(&pop, &vec!(1.0, 3.0, 3.0, 2.0), 0);
+
+    // the duplicate of individual 1 is only kept once
+    assert_eq!(hall_of_fame.members.len(), 2);
+    assert_eq!(hall_of_fame.best().unwrap().ind, Ind(vec!(1)));
+    assert_eq!(hall_of_fame.members[1].ind, Ind(vec!(2)));
+
+    let pop = Pop(vec!(Ind(vec!(3)), Ind(vec!(1)), Ind(vec!(4)), Ind(vec!(5))));
+    hall_of_fame.update(&pop, &vec!(2.5, 4.0, 0.0, 0.0), 1);
+    assert_eq!(hall_of_fame.members[0], Famer { ind: Ind(vec!(1)), fitness: 3.0, generation: 0 });
+    assert_eq!(hall_of_fame.members[1], Famer { ind: Ind(vec!(3)), fitness: 2.5, generation: 1 });
+
+    let mut pop = Pop(vec!(Ind(vec!(7)), Ind(vec!(7)), Ind(vec!(7))));
+    assert_eq!(hall_of_fame.reinsert(&mut pop, 2), vec!(2, 1));
+    assert_eq!(pop, Pop(vec!(Ind(vec!(7)), Ind(vec!(3)), Ind(vec!(1)))));
+}
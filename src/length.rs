@@ -0,0 +1,293 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use rand::prelude::*;
+
+use num::{PrimInt, FromPrimitive};
+
+use types::*;
+use stage::*;
+use crossover::*;
+
+
+// How crossover keeps variable-length individuals within their bounds.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum LengthCrossover {
+    // exchange a segment at the same positions in both individuals, within the length
+    // they share, so neither individual changes length
+    Homologous,
+    // cut each individual at its own point and exchange their tails, only using cut
+    // points that leave both children within the bounds
+    #[default]
+    TwoCut,
+}
+
+// Bounds and operators for variable-length individuals, measured in symbols.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LengthParams {
+    pub min_size: usize,
+    pub max_size: usize,
+
+    // the probability of each individual having a random symbol inserted, a symbol
+    // deleted, or a segment duplicated, in that order
+    pub prob_insertion: f64,
+    pub prob_deletion: f64,
+    pub prob_duplication: f64,
+
+    pub crossover: LengthCrossover,
+    pub prob_crossover: f64,
+}
+
+impl Default for LengthParams {
+    fn default() -> LengthParams {
+        LengthParams {
+            min_size: 10,
+            max_size: 200,
+            prob_insertion: 0.05,
+            prob_deletion: 0.05,
+            prob_duplication: 0.01,
+            crossover: LengthCrossover::default(),
+            prob_crossover: 0.6,
+        }
+    }
+}
+
+impl LengthParams {
+    pub fn check(&self) {
+        assert!(self.min_size > 0 && self.min_size <= self.max_size,
+                "Individual sizes must be between {} and {}, which is not a valid range!", self.min_size, self.max_size);
+    }
+}
+
+// a random symbol using the low bits_per_sym bits of a word
+fn random_symbol<T: PrimInt + FromPrimitive, R: Rng>(bits_per_sym: usize, rng: &mut R) -> T {
+    T::from_u64(rng.gen::<u64>() & low_bit_mask::<u64>(bits_per_sym)).unwrap()
+}
+
+// insert a random symbol at a random position of each individual with probability prob,
+// unless it is already max_size symbols long
+pub fn insertion_mutation<T, R>(pop: &mut Pop<T>, bits_per_sym: usize, max_size: usize, prob: f64, rng: &mut R)
+    where T: PrimInt + FromPrimitive,
+          R: Rng {
+    for ind in pop.0.iter_mut() {
+        if rng.gen::<f64>() < prob && ind.0.len() < max_size {
+            let position = rng.gen_range(0, ind.0.len() + 1);
+            ind.0.insert(position, random_symbol(bits_per_sym, rng));
+        }
+    }
+}
+
+// remove a random symbol from each individual with probability prob, unless it is
+// already min_size symbols long
+pub fn deletion_mutation<T, R>(pop: &mut Pop<T>, min_size: usize, prob: f64, rng: &mut R)
+    where R: Rng {
+    for ind in pop.0.iter_mut() {
+        if rng.gen::<f64>() < prob && ind.0.len() > min_size {
+            let position = rng.gen_range(0, ind.0.len());
+            ind.0.remove(position);
+        }
+    }
+}
+
+// With probability prob, copy a random segment of each individual and insert the copy
+// directly after the segment. The segment is shortened so that the individual stays
+// within max_size symbols.
+pub fn duplication_mutation<T, R>(pop: &mut Pop<T>, max_size: usize, prob: f64, rng: &mut R)
+    where T: Copy,
+          R: Rng {
+    for ind in pop.0.iter_mut() {
+        let ind_len = ind.0.len();
+        if rng.gen::<f64>() < prob && ind_len > 0 && ind_len < max_size {
+            let start = rng.gen_range(0, ind_len);
+            let end = rng.gen_range(start + 1, ind_len + 1).min(start + max_size - ind_len);
+
+            let segment: Vec<T> = ind.0[start..end].to_vec();
+            ind.0.splice(end..end, segment);
+        }
+    }
+}
+
+// apply insertion, deletion and duplication mutation with the probabilities in length
+pub fn length_mutation<T, R>(pop: &mut Pop<T>, bits_per_sym: usize, length: &LengthParams, rng: &mut R)
+    where T: PrimInt + FromPrimitive,
+          R: Rng {
+    insertion_mutation(pop, bits_per_sym, length.max_size, length.prob_insertion, rng);
+    deletion_mutation(pop, length.min_size, length.prob_deletion, rng);
+    duplication_mutation(pop, length.max_size, length.prob_duplication, rng);
+}
+
+// Two point crossover of neighbouring pairs within the symbols they share, cutting where
+// cut_mode allows. Both individuals keep their lengths.
+pub fn crossover_homologous<T, R>(pop: &mut Pop<T>, bits_per_sym: usize, cut_mode: CutMode, pc: f64, rng: &mut R)
+    where T: PrimInt,
+          R: Rng {
+    for pair in pop.0.chunks_mut(2) {
+        if pair.len() != 2 {
+            break;
+        }
+
+        if rng.gen::<f64>() < pc {
+            let shared_len = pair[0].0.len().min(pair[1].0.len());
+            let num_cut_points = cut_mode.num_cut_points(shared_len, bits_per_sym);
+
+            let mut cross_points = [cut_mode.bit_position(rng.gen_range(0, num_cut_points), bits_per_sym),
+                                    cut_mode.bit_position(rng.gen_range(0, num_cut_points), bits_per_sym)];
+            cross_points.sort();
+            cross_at_sorted_points(pair, bits_per_sym, &cross_points);
+        }
+    }
+}
+
+// The range of cut points in an individual of other_len symbols that, exchanged with the
+// tail after cut in an individual of ind_len symbols, leave both children between min_size
+// and max_size symbols. None if there are no such cut points.
+pub fn two_cut_range(ind_len: usize, cut: usize, other_len: usize, min_size: usize, max_size: usize) -> Option<(usize, usize)> {
+    // the first child is ind[..cut] ++ other[other_cut..], and the second child is
+    // other[..other_cut] ++ ind[cut..]
+    let tail_len = ind_len - cut;
+
+    let low = (other_len + cut).saturating_sub(max_size).max(min_size.saturating_sub(tail_len));
+    let high = other_len.min((other_len + cut).saturating_sub(min_size)).min(max_size.saturating_sub(tail_len));
+
+    if low <= high {
+        Some((low, high))
+    } else {
+        None
+    }
+}
+
+// One point crossover of neighbouring pairs at a separate cut point in each individual,
+// so the children exchange tails of different lengths. The second cut point is chosen so
+// that both children are between min_size and max_size symbols.
+pub fn crossover_two_cut<T, R>(pop: &mut Pop<T>, min_size: usize, max_size: usize, pc: f64, rng: &mut R)
+    where T: Copy,
+          R: Rng {
+    for pair in pop.0.chunks_mut(2) {
+        if pair.len() != 2 {
+            break;
+        }
+
+        if rng.gen::<f64>() < pc {
+            let cut = rng.gen_range(0, pair[0].0.len() + 1);
+            if let Some((low, high)) = two_cut_range(pair[0].0.len(), cut, pair[1].0.len(), min_size, max_size) {
+                let other_cut = rng.gen_range(low, high + 1);
+
+                let tail: Vec<T> = pair[0].0.split_off(cut);
+                let other_tail: Vec<T> = pair[1].0.split_off(other_cut);
+                pair[0].0.extend(other_tail);
+                pair[1].0.extend(tail);
+            }
+        }
+    }
+}
+
+// cross the population with the crossover in length, keeping lengths within its bounds
+pub fn length_crossover<T, R>(pop: &mut Pop<T>, bits_per_sym: usize, cut_mode: CutMode, length: &LengthParams, rng: &mut R)
+    where T: PrimInt,
+          R: Rng {
+    match length.crossover {
+        LengthCrossover::Homologous => {
+            crossover_homologous(pop, bits_per_sym, cut_mode, length.prob_crossover, rng);
+        },
+
+        LengthCrossover::TwoCut => {
+            crossover_two_cut(pop, length.min_size, length.max_size, length.prob_crossover, rng);
+        },
+    }
+}
+
+pub struct LengthMutationState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub bits_used: usize,
+    pub length: LengthParams,
+}
+
+impl<T> LengthMutationState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               bits_used: usize,
+               length: LengthParams) -> LengthMutationState<T> {
+        LengthMutationState { population, bits_used, length }
+    }
+}
+
+pub fn length_mutation_stage<S, T, R>(getter: Getter<S, LengthMutationState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + FromPrimitive + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let mutation_state = getter(state);
+        length_mutation(&mut mutation_state.population.borrow_mut(),
+                        mutation_state.bits_used,
+                        &mutation_state.length,
+                        rng);
+    });
+
+    f
+}
+
+pub struct LengthCrossoverState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub bits_used: usize,
+    pub cut_mode: CutMode,
+    pub length: LengthParams,
+}
+
+impl<T> LengthCrossoverState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               bits_used: usize,
+               cut_mode: CutMode,
+               length: LengthParams) -> LengthCrossoverState<T> {
+        LengthCrossoverState { population, bits_used, cut_mode, length }
+    }
+}
+
+pub fn length_crossover_stage<S, T, R>(getter: Getter<S, LengthCrossoverState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let cross_state = getter(state);
+        length_crossover(&mut cross_state.population.borrow_mut(),
+                         cross_state.bits_used,
+                         cross_state.cut_mode,
+                         &cross_state.length,
+                         rng);
+    });
+
+    f
+}
+
+#[test]
+fn test_variable_length_operators() {
+    let mut rng = R::seed_from_u64(0);
+
+    let mut pop = Pop(vec!(Ind(vec!(1u8, 2, 3)), Ind(vec!(4u8, 5, 6, 7, 0))));
+    for _ in 0..50 {
+        length_mutation(&mut pop, 3, &LengthParams { min_size: 2, max_size: 6, prob_insertion: 0.5, prob_deletion: 0.5, prob_duplication: 0.5, ..LengthParams::default() }, &mut rng);
+        assert!(pop.0.iter().all(|ind| ind.0.len() >= 2 && ind.0.len() <= 6));
+        assert!(pop.0.iter().all(|ind| ind.0.iter().all(|sym| *sym < 8)));
+    }
+
+    let mut pop = Pop(vec!(Ind(vec!(1u8, 2, 3)), Ind(vec!(4u8, 5, 6, 7, 8))));
+    duplication_mutation(&mut pop, 4, 1.0, &mut rng);
+    assert_eq!(pop.0[0].0.len(), 4);
+    assert_eq!(pop.0[1].0.len(), 5);
+
+    assert_eq!(two_cut_range(3, 1, 5, 2, 6), Some((0, 4)));
+    assert_eq!(two_cut_range(3, 3, 5, 2, 6), Some((2, 5)));
+    assert_eq!(two_cut_range(3, 0, 3, 3, 3), Some((0, 0)));
+
+    let original = Pop(vec!(Ind(vec!(0u8; 3)), Ind(vec!(1u8; 5))));
+    for _ in 0..50 {
+        let mut pop = original.clone();
+        crossover_two_cut(&mut pop, 2, 6, 1.0, &mut rng);
+        assert!(pop.0.iter().all(|ind| ind.0.len() >= 2 && ind.0.len() <= 6));
+        assert_eq!(pop.0[0].0.len() + pop.0[1].0.len(), 8);
+
+        let mut pop = original.clone();
+        crossover_homologous(&mut pop, 1, CutMode::Symbol, 1.0, &mut rng);
+        assert_eq!(pop.0[0].0.len(), 3);
+        assert_eq!(pop.0[1].0.len(), 5);
+        assert_eq!(&pop.0[1].0[3..], &[1, 1]);
+    }
+}
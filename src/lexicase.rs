@@ -0,0 +1,228 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+
+use rand::prelude::*;
+
+use types::*;
+use stage::*;
+use selection::*;
+use scaling::*;
+
+
+// Variants of lexicase selection. Each works on a vector of errors per
+// individual, one per fitness case, where lower errors are better.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Lexicase {
+    #[default]
+    Standard,
+    // individuals within an epsilon of the best error on a case survive it, with each
+    // case's epsilon set to the median absolute deviation of the population's errors
+    Epsilon,
+    // each generation selects on a random fraction of the cases
+    DownSampled { fraction: f64 },
+}
+
+// a single fitness for each individual, 1 / (1 + total error), for reporting
+// and elitism. A NaN total error is the worst, with fitness 0.
+pub fn case_fitnesses(errors: &[Vec<f64>]) -> Vec<f64> {
+    let mut fitnesses: Vec<f64> = errors.iter().map(|case_errors| {
+        let total = case_errors.iter().sum::<f64>();
+        if total.is_nan() { f64::INFINITY } else { total }
+    }).collect();
+    minimize_to_maximize(&mut fitnesses);
+    fitnesses
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_unstable_by(|value, other| value.partial_cmp(other).unwrap_or(Ordering::Equal));
+
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+// the median absolute deviation of each case's errors across the population
+pub fn mad_epsilons(errors: &[Vec<f64>]) -> Vec<f64> {
+    let num_cases = errors[0].len();
+
+    let mut epsilons = Vec::with_capacity(num_cases);
+    let mut case_errors = Vec::with_capacity(errors.len());
+    for case in 0..num_cases {
+        case_errors.clear();
+        case_errors.extend(errors.iter().map(|ind_errors| ind_errors[case]));
+        let case_median = median(&mut case_errors);
+
+        for error in case_errors.iter_mut() {
+            *error = (*error - case_median).abs();
+        }
+        epsilons.push(median(&mut case_errors));
+    }
+
+    epsilons
+}
+
+// Select one individual by filtering the population on each of the given cases in a
+// random order. With epsilons, individuals within epsilons[case] of the best error
+// survive that case. Ties left after every case are broken uniformly at random.
+pub fn lexicase_index<R: Rng>(errors: &[Vec<f64>], cases: &mut [usize], epsilons: Option<&[f64]>, rng: &mut R) -> usize {
+    let mut candidates: Vec<usize> = (0..errors.len()).collect();
+
+    cases.shuffle(rng);
+    for case in cases.iter() {
+        if candidates.len() == 1 {
+            break;
+        }
+
+        let best = candidates.iter().map(|index| errors[*index][*case]).fold(f64::INFINITY, f64::min);
+        let threshold = best + epsilons.map_or(0.0, |epsilons| epsilons[*case]);
+
+        // NaN errors never pass the threshold, so they are the worst errors, but a case that
+        // every candidate fails this way is skipped rather than leaving no candidates
+        if candidates.iter().any(|index| errors[*index][*case] <= threshold) {
+            candidates.retain(|index| errors[*index][*case] <= threshold);
+        }
+    }
+
+    candidates[rng.gen_range(0, candidates.len())]
+}
+
+// indices of the individuals selected for the next generation, with the elitism
+// individuals of least total error first
+pub fn lexicase_indices<R: Rng>(errors: &[Vec<f64>], lexicase: Lexicase, elitism: usize, rng: &mut R) -> Vec<usize> {
+    let num_inds = errors.len();
+    let num_cases = errors[0].len();
+
+    let mut selected = k_elite(&case_fitnesses(errors), elitism);
+    selected.truncate(num_inds);
+
+    let mut all_cases: Vec<usize> = (0..num_cases).collect();
+
+    let epsilons = match lexicase {
+        Lexicase::Epsilon => Some(mad_epsilons(errors)),
+        _ => None,
+    };
+
+    let mut cases = match lexicase {
+        Lexicase::DownSampled { fraction } => {
+            let num_sampled = ((fraction * num_cases as f64).ceil() as usize).max(1).min(num_cases);
+            all_cases.partial_shuffle(rng, num_sampled).0.to_vec()
+        },
+
+        _ => all_cases,
+    };
+
+    while selected.len() < num_inds {
+        selected.push(lexicase_index(errors, &mut cases, epsilons.as_deref(), rng));
+    }
+
+    selected
+}
+
+// fill new_pop by lexicase selection, as stochastic_universal_sampling does by fitness
+pub fn lexicase_selection<R: Rng, T: Copy>(pop: &Pop<T>, new_pop: &mut Pop<T>, errors: &[Vec<f64>], lexicase: Lexicase, elitism: usize, rng: &mut R) {
+    let selected = lexicase_indices(errors, lexicase, elitism, rng);
+    copy_selected(pop, new_pop, &selected);
+}
+
+pub struct LexicaseState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub alt_population: Rc<RefCell<Pop<T>>>,
+    pub errors: Rc<RefCell<Vec<Vec<f64>>>>,
+    pub lexicase: Lexicase,
+    pub elitism: usize,
+}
+
+impl<T> LexicaseState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               alt_population: Rc<RefCell<Pop<T>>>,
+               errors: Rc<RefCell<Vec<Vec<f64>>>>,
+               lexicase: Lexicase,
+               elitism: usize) -> LexicaseState<T> {
+        LexicaseState {
+            population,
+            alt_population,
+            errors,
+            lexicase,
+            elitism,
+        }
+    }
+}
+
+pub fn lexicase_stage<S, T, R>(getter: Getter<S, LexicaseState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: Copy + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let lexicase_state = getter(state);
+        lexicase_selection(&lexicase_state.population.borrow(),
+                           &mut lexicase_state.alt_population.borrow_mut(),
+                           &lexicase_state.errors.borrow(),
+                           lexicase_state.lexicase,
+                           lexicase_state.elitism,
+                           rng);
+        lexicase_state.population.swap(&lexicase_state.alt_population);
+    });
+
+    f
+}
+
+#[test]
+fn test_lexicase_selection() {
+    let mut rng = R::seed_from_u64(0);
+
+    // individual 0 is the only one that solves case 2, and individual 1 solves the others
+    let errors = vec!(vec!(1.0, 1.0, 0.0),
+                      vec!(0.0, 0.0, 1.0),
+                      vec!(2.0, 2.0, 2.0));
+
+    let selected = lexicase_indices(&errors, Lexicase::Standard, 0, &mut rng);
+    assert_eq!(selected.len(), 3);
+    assert!(selected.iter().all(|index| *index != 2));
+
+    let mut cases = vec!(2);
+    assert_eq!(lexicase_index(&errors, &mut cases, None, &mut rng), 0);
+
+    // within epsilon of the best, individuals 0 and 1 both survive every case
+    assert_eq!(mad_epsilons(&errors), vec!(1.0, 1.0, 1.0));
+    let selected = lexicase_indices(&errors, Lexicase::Epsilon, 1, &mut rng);
+    assert_eq!(selected[0], 1);
+
+    let selected = lexicase_indices(&errors, Lexicase::DownSampled { fraction: 0.5 }, 0, &mut rng);
+    assert!(selected.iter().all(|index| *index != 2));
+
+    let pop = Pop(vec!(Ind(vec!(0u8)), Ind(vec!(1u8)), Ind(vec!(2u8))));
+    let mut new_pop = Pop(vec!(Ind(vec!(9u8)); 3));
+    lexicase_selection(&pop, &mut new_pop, &errors, Lexicase::Standard, 0, &mut rng);
+    assert!(new_pop.0.iter().all(|ind| ind.0[0] < 2));
+}
+
+#[test]
+fn test_lexicase_nan_errors() {
+    let mut rng = R::seed_from_u64(0);
+
+    // NaN is worse than any error, and a case where every candidate is NaN filters nothing
+    let errors = vec!(vec!(f64::NAN, 1.0),
+                      vec!(f64::NAN, 0.0),
+                      vec!(f64::NAN, f64::NAN));
+
+    for _ in 0..10 {
+        let mut cases = vec!(0, 1);
+        assert_eq!(lexicase_index(&errors, &mut cases, None, &mut rng), 1);
+    }
+
+    let mut cases = vec!(0);
+    assert!(lexicase_index(&errors, &mut cases, None, &mut rng) < 3);
+
+    assert_eq!(case_fitnesses(&errors), vec!(0.0, 0.0, 0.0));
+
+    let selected = lexicase_indices(&errors, Lexicase::Standard, 0, &mut rng);
+    assert!(selected.iter().all(|index| *index == 1));
+
+    for lexicase in [Lexicase::Epsilon, Lexicase::DownSampled { fraction: 0.5 }] {
+        assert_eq!(lexicase_indices(&errors, lexicase, 1, &mut rng).len(), 3);
+    }
+}
@@ -15,6 +15,9 @@ pub use crossover::*;
 pub mod rotation;
 pub use rotation::*;
 
+pub mod transposition;
+pub use transposition::*;
+
 pub mod point_mutation;
 pub use point_mutation::*;
 
@@ -24,6 +27,36 @@ pub use ops::*;
 pub mod selection;
 pub use selection::*;
 
+pub mod replacement;
+pub use replacement::*;
+
+pub mod scaling;
+pub use scaling::*;
+
+pub mod lexicase;
+pub use lexicase::*;
+
+pub mod nsga;
+pub use nsga::*;
+
+pub mod hall_of_fame;
+pub use hall_of_fame::*;
+
+pub mod niching;
+pub use niching::*;
+
+pub mod alps;
+pub use alps::*;
+
+pub mod mating;
+pub use mating::*;
+
+pub mod length;
+pub use length::*;
+
+pub mod adaptive;
+pub use adaptive::*;
+
 pub mod evaluation;
 pub use evaluation::*;
 
@@ -39,3 +72,11 @@ pub use rgep::*;
 pub mod stage;
 pub use stage::*;
 
+pub mod termination;
+pub use termination::*;
+
+pub mod observer;
+pub use observer::*;
+
+#[cfg(test)] mod test_support;
+
@@ -69,6 +69,7 @@ fn main_popcount() {
         ind_size: 10,
         num_gens: 1000,
         elitism: 1,
+        ..RgepParams::default()
     };
 
     let context = Context {
@@ -81,9 +82,8 @@ fn main_popcount() {
     println!("bytes = {}", context.bytes_per_sym());
     
     let default = context.default.clone();
-    let eval_prog: &EvalFunction<u32, u32, ThreadRng> =
-        &move |prog: &Program<u32, u32>, _state: &mut u32, _r: &mut ThreadRng| -> f64 {
-            let mut rng = thread_rng();
+    let eval_prog: &EvalFunction<u32, u32, R> =
+        &move |prog: &Program<u32, u32>, _state: &mut u32, rng: &mut R| -> f64 {
             let mut penalty: f64 = 1.0;
 
             for _ in 0..100 {
@@ -100,36 +100,18 @@ fn main_popcount() {
             1.0 / penalty
         };
 
-    let pop = rgep(&params,
-                   &context,
-                   &0,
-                   eval_prog,
-                   &mut thread_rng());
+    let result = rgep(&params,
+                      &context,
+                      &0,
+                      eval_prog);
 
-    let mut rng = thread_rng();
-
-    let mut variables: Variables<u32> = HashMap::new();
-    variables.insert("x".to_string(), 3);
-
-    let mut fitnesses = Vec::new();
-    for ind in pop.0.iter() {
-        let fitness = eval_prog(&context.compile(&ind), &mut 0, &mut rng);
-        fitnesses.push(fitness);
-        println!("{} -> {}", context.to_string(&ind), fitness);
-    }
-
-    let index_fittest = fittest(&fitnesses);
-    //let fittest = pop.0[index_fittest].clone();
-    let fitness = fitnesses[index_fittest];
-
-    println!("best fitness    = {}", fitness);
-    println!("best individual = {:?}", context.to_string(&pop.0[index_fittest]));
+    println!("best fitness    = {}", result.best_fitness);
+    println!("best individual = {:?}", result.best_string);
 
     let words = vec!(0xA5A5, 0x1234, 0x1010, 0x0001);
     for mut word in words {
         let mut stack = vec!(word);
-        let mut prog = Program(Vec::with_capacity(pop.0[0].0.len()));
-        context.compile_to(&pop.0[index_fittest], &mut prog);
+        let prog = context.compile(&result.best);
         let result = prog.eval_with_stack(&mut word, default.clone() as u32, &mut stack);
         println!("Expected {}, was {}", popcount(word), result);
     }
@@ -159,6 +141,7 @@ fn main_expr() {
         ind_size: 1000,
         num_gens: 100,
         elitism: 1,
+        ..RgepParams::default()
     };
 
     let context = Context {
@@ -173,11 +156,9 @@ fn main_expr() {
     println!("bits = {}", context.bits_per_sym());
     println!("bytes = {}", context.bytes_per_sym());
     
-    let mut rng = thread_rng();
-
     let default = context.default.clone();
-    let eval_prog: &EvalFunction<Arith<u32>, Variables<u32>, ThreadRng> =
-        &move |prog: &Program<Arith<u32>, Variables<u32>>, state: &mut Variables<u32>, _r: &mut ThreadRng| -> f64 {
+    let eval_prog: &EvalFunction<Arith<u32>, Variables<u32>, R> =
+        &move |prog: &Program<Arith<u32>, Variables<u32>>, state: &mut Variables<u32>, _r: &mut R| -> f64 {
             let mut sample_points: Vec<(u32, u32)> = Vec::new();
             for x in (0..100).step_by(10) {
                 sample_points.push((x as u32, (x * x) as u32));
@@ -195,25 +176,12 @@ fn main_expr() {
             }
         };
 
-    let pop = rgep(&params,
-                   &context,
-                   &variables,
-                   eval_prog,
-                   &mut rng);
-
-    let mut fitnesses = Vec::new();
-    for ind in pop.0.iter() {
-        let fitness = eval_prog(&context.compile(&ind), &mut variables, &mut rng);
-        fitnesses.push(fitness);
-        println!("{} -> {}", context.to_string(&ind), fitness);
-    }
+    let result = rgep(&params,
+                      &context,
+                      &variables,
+                      eval_prog);
 
-    let index_fittest = fittest(&fitnesses);
-    //let fittest = pop.0[index_fittest].clone();
-    let fitness = fitnesses[index_fittest];
-
-    println!("best fitness    = {}", fitness);
-    println!("best individual = {:?}", context.to_string(&pop.0[index_fittest]));
-    println!("infix = {:?}", context.eval(&pop.0[index_fittest], &mut variables).simplify().to_string_infix());
+    println!("best fitness    = {}", result.best_fitness);
+    println!("best individual = {:?}", result.best_string);
+    println!("infix = {:?}", context.eval(&result.best, &mut variables).simplify().to_string_infix());
 }
-
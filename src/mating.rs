@@ -0,0 +1,219 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use rand::prelude::*;
+
+use num::PrimInt;
+
+use types::*;
+use stage::*;
+use niching::*;
+
+
+// How individuals are paired for crossover. Every crossover operator crosses neighbouring
+// individuals, so mating rearranges the population before crossover so that each pair of
+// neighbours are mates. The population's contents are never changed, only its order.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Mating {
+    // cross individuals with their neighbours, as they were selected
+    #[default]
+    Neighbours,
+    // pair individuals uniformly at random
+    Shuffled,
+    // each individual, in a random order, takes the fittest of size randomly chosen
+    // unpaired individuals as its mate
+    Tournament { size: usize },
+    // pair individuals at random, avoiding pairs that differ in threshold bits or fewer
+    // as in CHC. Pairs that cannot avoid it are not crossed.
+    IncestPrevention { threshold: usize },
+}
+
+// The order to arrange the population in so that consecutive pairs are mates. fitnesses
+// gives the fitness of each individual, and is only used by Mating::Tournament, and
+// bits_used is the number of bits used in each word, for Mating::IncestPrevention.
+pub fn mating_order<T: PrimInt, R: Rng>(mating: Mating, pop: &Pop<T>, fitnesses: &[f64], bits_used: usize, rng: &mut R) -> Vec<usize> {
+    let num_inds = pop.0.len();
+    let mut order: Vec<usize> = (0..num_inds).collect();
+
+    match mating {
+        Mating::Neighbours => {},
+
+        Mating::Shuffled => {
+            order.shuffle(rng);
+        },
+
+        Mating::Tournament { size } => {
+            assert!(size > 0, "Mating tournaments must have at least one individual!");
+
+            order.shuffle(rng);
+            let mut position = 0;
+            while position + 1 < num_inds {
+                let mut mate_position = rng.gen_range(position + 1, num_inds);
+                for _ in 1..size {
+                    let candidate = rng.gen_range(position + 1, num_inds);
+                    if fitnesses[order[candidate]] > fitnesses[order[mate_position]] {
+                        mate_position = candidate;
+                    }
+                }
+
+                order.swap(position + 1, mate_position);
+                position += 2;
+            }
+        },
+
+        Mating::IncestPrevention { threshold } => {
+            order.shuffle(rng);
+            let mut position = 0;
+            while position + 1 < num_inds {
+                let first = &pop.0[order[position]];
+                if hamming_distance(first, &pop.0[order[position + 1]], bits_used) <= threshold {
+                    let mate_position = ((position + 2)..num_inds).find(|candidate| {
+                        hamming_distance(first, &pop.0[order[*candidate]], bits_used) > threshold
+                    });
+
+                    if let Some(mate_position) = mate_position {
+                        order.swap(position + 1, mate_position);
+                    }
+                }
+
+                position += 2;
+            }
+        },
+    }
+
+    order
+}
+
+// put the population and its fitnesses in the given order
+pub fn arrange<T: Clone>(pop: &mut Pop<T>, fitnesses: &mut Vec<f64>, order: &[usize]) {
+    let arranged_pop = order.iter().map(|index| pop.0[*index].clone()).collect();
+    let arranged_fitnesses = order.iter().map(|index| fitnesses[*index]).collect();
+
+    pop.0 = arranged_pop;
+    *fitnesses = arranged_fitnesses;
+}
+
+// Pair the population with the given mating scheme, and then apply crossover. With
+// Mating::IncestPrevention, any pairs that are still too similar are restored afterwards,
+// so that they are left uncrossed whichever crossover operator is used. Returns the order
+// the population was arranged in, so that pop.0[index] was crossed from individual order[index].
+pub fn mate<T, R>(mating: Mating,
+                  pop: &mut Pop<T>,
+                  fitnesses: &mut Vec<f64>,
+                  bits_used: usize,
+                  crossover: &mut dyn FnMut(&mut Pop<T>, &mut R),
+                  rng: &mut R) -> Vec<usize>
+    where T: PrimInt,
+          R: Rng {
+    let order = if mating == Mating::Neighbours {
+        (0..pop.0.len()).collect()
+    } else {
+        let order = mating_order(mating, pop, fitnesses, bits_used, rng);
+        arrange(pop, fitnesses, &order);
+        order
+    };
+
+    let mut uncrossed = Vec::new();
+    if let Mating::IncestPrevention { threshold } = mating {
+        for (pair_index, pair) in pop.0.chunks(2).enumerate() {
+            if pair.len() == 2 && hamming_distance(&pair[0], &pair[1], bits_used) <= threshold {
+                uncrossed.push((pair_index, pair[0].clone(), pair[1].clone()));
+            }
+        }
+    }
+
+    crossover(pop, rng);
+
+    for (pair_index, first, second) in uncrossed {
+        pop.0[2 * pair_index] = first;
+        pop.0[2 * pair_index + 1] = second;
+    }
+
+    order
+}
+
+// fitnesses must be those of the individuals in the population, such as the fitnesses of
+// the individuals they were selected from
+pub struct MatingState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub fitnesses: Rc<RefCell<Vec<f64>>>,
+    pub mating: Mating,
+    pub bits_used: usize,
+}
+
+impl<T> MatingState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               fitnesses: Rc<RefCell<Vec<f64>>>,
+               mating: Mating,
+               bits_used: usize) -> MatingState<T> {
+        MatingState {
+            population,
+            fitnesses,
+            mating,
+            bits_used,
+        }
+    }
+}
+
+// Wrap a crossover stage so that it crosses mates chosen by the getter's mating scheme.
+// The crossover stage must work on the same population as the mating state.
+pub fn mating_stage<S, T, R>(getter: Getter<S, MatingState<T>>, crossover: Stage<S, R>) -> Stage<S, R>
+    where R: Rng + 'static,
+          T: PrimInt + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let mating_state = getter(state);
+
+        let mut pop = mating_state.population.borrow().clone();
+        let mut fitnesses = mating_state.fitnesses.borrow().clone();
+        mate(mating_state.mating, &mut pop, &mut fitnesses, mating_state.bits_used, &mut |arranged_pop: &mut Pop<T>, rng: &mut R| {
+            std::mem::swap(&mut *mating_state.population.borrow_mut(), arranged_pop);
+            crossover(state, rng);
+            std::mem::swap(&mut *mating_state.population.borrow_mut(), arranged_pop);
+        }, rng);
+
+        *mating_state.population.borrow_mut() = pop;
+        *mating_state.fitnesses.borrow_mut() = fitnesses;
+    });
+
+    f
+}
+
+#[test]
+fn test_mating_orders() {
+    use crossover::*;
+
+    let mut rng = R::seed_from_u64(0);
+
+    let pop = Pop(vec!(Ind(vec!(0x00u8)), Ind(vec!(0x00u8)), Ind(vec!(0xFFu8)), Ind(vec!(0xFFu8))));
+    let fitnesses = vec!(1.0, 2.0, 3.0, 4.0);
+
+    for mating in vec!(Mating::Shuffled, Mating::Tournament { size: 3 }, Mating::IncestPrevention { threshold: 0 }) {
+        let mut order = mating_order(mating, &pop, &fitnesses, 8, &mut rng);
+        if let Mating::IncestPrevention { .. } = mating {
+            assert!(order.chunks(2).all(|pair| hamming_distance(&pop.0[pair[0]], &pop.0[pair[1]], 8) > 0));
+        }
+
+        order.sort();
+        assert_eq!(order, vec!(0, 1, 2, 3));
+    }
+
+    // identical neighbours are never crossed with incest prevention
+    let mut same_pop = Pop(vec!(Ind(vec!(0x00u8)), Ind(vec!(0x00u8))));
+    let mut same_fitnesses = vec!(1.0, 1.0);
+    mate(Mating::IncestPrevention { threshold: 0 }, &mut same_pop, &mut same_fitnesses, 8, &mut |pop: &mut Pop<u8>, rng: &mut R| {
+        crossover_uniform_bits(pop, 8, 1.0, 1.0, rng);
+        assert_eq!(pop.0[0], pop.0[1]);
+        pop.0[0] = Ind(vec!(0x01));
+    }, &mut rng);
+    assert_eq!(same_pop, Pop(vec!(Ind(vec!(0x00u8)), Ind(vec!(0x00u8)))));
+
+    // shuffled fitnesses stay with their individuals
+    let mut shuffled_pop = Pop(vec!(Ind(vec!(0u8)), Ind(vec!(1u8)), Ind(vec!(2u8)), Ind(vec!(3u8))));
+    let mut shuffled_fitnesses = fitnesses.clone();
+    let order = mate(Mating::Shuffled, &mut shuffled_pop, &mut shuffled_fitnesses, 8, &mut |_pop: &mut Pop<u8>, _rng: &mut R| {}, &mut rng);
+    for ((ind, fitness), index) in shuffled_pop.0.iter().zip(shuffled_fitnesses.iter()).zip(order.iter()) {
+        assert_eq!(*fitness, ind.0[0] as f64 + 1.0);
+        assert_eq!(ind.0[0] as usize, *index);
+    }
+}
@@ -0,0 +1,217 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use rand::prelude::*;
+
+use num::PrimInt;
+
+use types::*;
+use stage::*;
+
+
+// Niching keeps a population spread over several optima instead of collapsing onto one.
+// Fitness sharing is applied to the fitnesses before selection, like FitnessScaling,
+// and crowding is available as a ReplacementPolicy for steady-state runs.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Niching {
+    #[default]
+    None,
+    // share fitness between individuals less than radius apart by the given distance
+    Sharing { radius: f64, alpha: f64, distance: Distance },
+}
+
+impl Niching {
+    pub fn apply<T: PrimInt>(&self, pop: &Pop<T>, bits_used: usize, fitnesses: &mut [f64]) {
+        match self {
+            Niching::None => {},
+
+            Niching::Sharing { radius, alpha, distance } => {
+                fitness_sharing(fitnesses, *radius, *alpha, &|index, other| {
+                    distance.between(&pop.0[index], &pop.0[other], bits_used)
+                });
+            },
+        }
+    }
+}
+
+// The distance between two individuals used by fitness sharing.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Distance {
+    // the number of differing bits
+    #[default]
+    Hamming,
+    // the number of differing words, which are the genes of a GA individual and the
+    // symbols of an RGEP individual
+    Genes,
+}
+
+impl Distance {
+    pub fn between<T: PrimInt>(&self, ind: &Ind<T>, other: &Ind<T>, bits_used: usize) -> f64 {
+        match self {
+            Distance::Hamming => hamming_distance(ind, other, bits_used) as f64,
+
+            Distance::Genes => gene_distance(ind, other) as f64,
+        }
+    }
+}
+
+// The number of bits that differ between two individuals using bits_used bits of each
+// word. Words past the end of the shorter individual count as entirely different.
+pub fn hamming_distance<T: PrimInt>(ind: &Ind<T>, other: &Ind<T>, bits_used: usize) -> usize {
+    let mut distance: usize = ind.0.iter()
+                                   .zip(other.0.iter())
+                                   .map(|(word, other_word)| (*word ^ *other_word).count_ones() as usize)
+                                   .sum();
+
+    let num_extra = ind.0.len().abs_diff(other.0.len());
+    distance += num_extra * bits_used;
+
+    distance
+}
+
+// The number of words that differ between two individuals. Words past the end of the
+// shorter individual all count as different.
+pub fn gene_distance<T: PrimInt>(ind: &Ind<T>, other: &Ind<T>) -> usize {
+    let num_different = ind.0.iter()
+                             .zip(other.0.iter())
+                             .filter(|(word, other_word)| word != other_word)
+                             .count();
+
+    num_different + ind.0.len().abs_diff(other.0.len())
+}
+
+// Divide each fitness by its niche count, the sum over the population of
+// 1 - (d / radius)^alpha for each individual closer than radius (including itself).
+// distance(index, other) gives the distance between two individuals. Fitnesses
+// should not be negative, as with stochastic universal sampling.
+pub fn fitness_sharing(fitnesses: &mut [f64], radius: f64, alpha: f64, distance: &dyn Fn(usize, usize) -> f64) {
+    let num_inds = fitnesses.len();
+
+    let mut niche_counts = vec![1.0; num_inds];
+    for index in 0..num_inds {
+        for other in (index + 1)..num_inds {
+            let dist = distance(index, other);
+            if dist < radius {
+                let share = 1.0 - (dist / radius).powf(alpha);
+                niche_counts[index] += share;
+                niche_counts[other] += share;
+            }
+        }
+    }
+
+    for (fitness, niche_count) in fitnesses.iter_mut().zip(niche_counts.iter()) {
+        *fitness /= niche_count;
+    }
+}
+
+// Of window individuals chosen at random, the index of the one closest to child.
+// The child then competes only with that individual, as in restricted tournament selection.
+pub fn restricted_tournament_index<T: PrimInt, R: Rng>(pop: &Pop<T>, child: &Ind<T>, window: usize, bits_used: usize, rng: &mut R) -> usize {
+    let num_inds = pop.0.len();
+
+    let mut closest_index = rng.gen_range(0, num_inds);
+    let mut closest_distance = hamming_distance(child, &pop.0[closest_index], bits_used);
+    for _ in 1..window {
+        let index = rng.gen_range(0, num_inds);
+        let distance = hamming_distance(child, &pop.0[index], bits_used);
+        if distance < closest_distance {
+            closest_index = index;
+            closest_distance = distance;
+        }
+    }
+
+    closest_index
+}
+
+// Deterministic crowding pairs each child of two parents with the parent it is closest to.
+// Returns the (parent, child) positions for the children of parents[0] and parents[1].
+pub fn crowding_pairs<T: PrimInt>(parents: (&Ind<T>, &Ind<T>), children: (&Ind<T>, &Ind<T>), bits_used: usize) -> [(usize, usize); 2] {
+    let straight = hamming_distance(parents.0, children.0, bits_used) + hamming_distance(parents.1, children.1, bits_used);
+    let crossed = hamming_distance(parents.0, children.1, bits_used) + hamming_distance(parents.1, children.0, bits_used);
+
+    if straight <= crossed {
+        [(0, 0), (1, 1)]
+    } else {
+        [(0, 1), (1, 0)]
+    }
+}
+
+pub struct SharingState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub fitnesses: Rc<RefCell<Vec<f64>>>,
+    pub niching: Niching,
+    pub bits_used: usize,
+}
+
+impl<T> SharingState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               fitnesses: Rc<RefCell<Vec<f64>>>,
+               niching: Niching,
+               bits_used: usize) -> SharingState<T> {
+        SharingState {
+            population,
+            fitnesses,
+            niching,
+            bits_used,
+        }
+    }
+}
+
+// share the fitnesses in place, for use between evaluation and selection
+pub fn sharing_stage<S, T, R>(getter: Getter<S, SharingState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, _rng| {
+        let sharing_state = getter(state);
+        sharing_state.niching.apply(&sharing_state.population.borrow(),
+                                    sharing_state.bits_used,
+                                    &mut sharing_state.fitnesses.borrow_mut());
+    });
+
+    f
+}
+
+#[test]
+fn test_fitness_sharing() {
+    let pop = Pop(vec!(Ind(vec!(0x00u8, 0x00)),
+                       Ind(vec!(0x01u8, 0x00)),
+                       Ind(vec!(0xFFu8, 0xFF))));
+
+    assert_eq!(hamming_distance(&pop.0[0], &pop.0[1], 8), 1);
+    assert_eq!(hamming_distance(&pop.0[0], &pop.0[2], 8), 16);
+    assert_eq!(hamming_distance(&pop.0[0], &Ind(vec!(0x00u8)), 8), 8);
+
+    // a missing word only counts the bits in use
+    assert_eq!(hamming_distance(&Ind(vec!(0x01u8, 0x02)), &Ind(vec!(0x01u8)), 2), 2);
+
+    // the two close individuals share their fitness, and the distant one keeps its own
+    let mut fitnesses = vec!(1.0, 1.0, 1.0);
+    Niching::Sharing { radius: 2.0, alpha: 1.0, distance: Distance::Hamming }.apply(&pop, 8, &mut fitnesses);
+    assert_eq!(fitnesses, vec!(1.0 / 1.5, 1.0 / 1.5, 1.0));
+
+    // by genes, the first two differ in one word and the third in both
+    assert_eq!(gene_distance(&pop.0[0], &pop.0[1]), 1);
+    assert_eq!(gene_distance(&pop.0[0], &pop.0[2]), 2);
+    assert_eq!(gene_distance(&pop.0[0], &Ind(vec!(0x00u8))), 1);
+
+    // so within a radius of 4, the third shares its fitness by genes but not by bits
+    let mut fitnesses = vec!(1.0, 1.0, 1.0);
+    Niching::Sharing { radius: 4.0, alpha: 1.0, distance: Distance::Hamming }.apply(&pop, 8, &mut fitnesses);
+    assert_eq!(fitnesses, vec!(1.0 / 1.75, 1.0 / 1.75, 1.0));
+
+    let mut fitnesses = vec!(1.0, 1.0, 1.0);
+    Niching::Sharing { radius: 4.0, alpha: 1.0, distance: Distance::Genes }.apply(&pop, 8, &mut fitnesses);
+    assert_eq!(fitnesses, vec!(1.0 / 2.25, 1.0 / 2.25, 1.0 / 2.0));
+
+    // any distance can be shared over, such as one between program outputs
+    let outputs: Vec<f64> = vec!(0.0, 5.0, 0.0);
+    let mut fitnesses = vec!(1.0, 1.0, 1.0);
+    fitness_sharing(&mut fitnesses, 1.0, 1.0, &|index, other| (outputs[index] - outputs[other]).abs());
+    assert_eq!(fitnesses, vec!(0.5, 1.0, 0.5));
+
+    let mut rng = R::seed_from_u64(0);
+    assert_eq!(restricted_tournament_index(&pop, &Ind(vec!(0xFE, 0xFF)), 100, 8, &mut rng), 2);
+
+    assert_eq!(crowding_pairs((&pop.0[0], &pop.0[2]), (&pop.0[2], &pop.0[1]), 8), [(0, 1), (1, 0)]);
+}
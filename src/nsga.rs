@@ -0,0 +1,240 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+
+use rand::prelude::*;
+
+use types::*;
+use stage::*;
+use selection::*;
+
+
+// Multi-objective selection by NSGA-II. Each individual has a vector of objectives,
+// all of which are minimized, such as a program's error and its length.
+
+// whether a is no worse than b in every objective and better in at least one
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    let mut better = false;
+    for (obj_a, obj_b) in a.iter().zip(b.iter()) {
+        if obj_a > obj_b {
+            return false;
+        }
+        better |= obj_a < obj_b;
+    }
+
+    better
+}
+
+// Sort the population into fronts of mutually non-dominated individuals. The first
+// front is the population's Pareto front, and each later front is only dominated
+// by individuals in the fronts before it.
+pub fn non_dominated_sort(objectives: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    let num_inds = objectives.len();
+
+    let mut dominated: Vec<Vec<usize>> = vec![Vec::new(); num_inds];
+    let mut num_dominating = vec![0; num_inds];
+
+    for index in 0..num_inds {
+        for other in (index + 1)..num_inds {
+            if dominates(&objectives[index], &objectives[other]) {
+                dominated[index].push(other);
+                num_dominating[other] += 1;
+            } else if dominates(&objectives[other], &objectives[index]) {
+                dominated[other].push(index);
+                num_dominating[index] += 1;
+            }
+        }
+    }
+
+    let mut fronts = Vec::new();
+    let mut front: Vec<usize> = (0..num_inds).filter(|index| num_dominating[*index] == 0).collect();
+    while !front.is_empty() {
+        let mut next_front = Vec::new();
+        for index in front.iter() {
+            for other in dominated[*index].iter() {
+                num_dominating[*other] -= 1;
+                if num_dominating[*other] == 0 {
+                    next_front.push(*other);
+                }
+            }
+        }
+
+        fronts.push(front);
+        front = next_front;
+    }
+
+    fronts
+}
+
+// The crowding distance of each member of a front, in the front's order. The extremes
+// of each objective get an infinite distance so that they are always kept.
+pub fn crowding_distance(objectives: &[Vec<f64>], front: &[usize]) -> Vec<f64> {
+    let mut distances = vec![0.0; front.len()];
+    if front.is_empty() {
+        return distances;
+    }
+
+    // each objective's values across the front, in the front's order
+    let num_objectives = objectives[front[0]].len();
+    let columns = (0..num_objectives).map(|objective| {
+        front.iter().map(|index| objectives[*index][objective]).collect::<Vec<f64>>()
+    });
+
+    let mut order: Vec<usize> = (0..front.len()).collect();
+    for column in columns {
+        let value = |position: usize| column[position];
+
+        order.sort_unstable_by(|position, other| value(*position).partial_cmp(&value(*other)).unwrap_or(Ordering::Equal));
+
+        let first = order[0];
+        let last = order[order.len() - 1];
+        distances[first] = f64::INFINITY;
+        distances[last] = f64::INFINITY;
+
+        let range = value(last) - value(first);
+        if range > 0.0 {
+            for window in order.windows(3) {
+                distances[window[1]] += (value(window[2]) - value(window[0])) / range;
+            }
+        }
+    }
+
+    distances
+}
+
+// the front number and crowding distance of every individual
+pub fn rank_and_crowding(objectives: &[Vec<f64>]) -> (Vec<usize>, Vec<f64>) {
+    let mut ranks = vec![0; objectives.len()];
+    let mut distances = vec![0.0; objectives.len()];
+
+    for (rank, front) in non_dominated_sort(objectives).iter().enumerate() {
+        for (index, distance) in front.iter().zip(crowding_distance(objectives, front)) {
+            ranks[*index] = rank;
+            distances[*index] = distance;
+        }
+    }
+
+    (ranks, distances)
+}
+
+// the individuals of the population's Pareto front
+pub fn pareto_front(objectives: &[Vec<f64>]) -> Vec<usize> {
+    non_dominated_sort(objectives).into_iter().next().unwrap_or_default()
+}
+
+// NSGA-II survival. Whole fronts are kept while they fit in num_select individuals,
+// and the front that does not fit is truncated to its least crowded members.
+pub fn nsga2_survivors(objectives: &[Vec<f64>], num_select: usize) -> Vec<usize> {
+    let mut survivors = Vec::with_capacity(num_select);
+
+    for front in non_dominated_sort(objectives) {
+        if survivors.len() + front.len() <= num_select {
+            survivors.extend(front);
+        } else {
+            let distances = crowding_distance(objectives, &front);
+            let mut order: Vec<usize> = (0..front.len()).collect();
+            order.sort_by(|position, other| distances[*other].partial_cmp(&distances[*position]).unwrap_or(Ordering::Equal));
+
+            let num_remaining = num_select - survivors.len();
+            survivors.extend(order.iter().take(num_remaining).map(|position| front[*position]));
+        }
+
+        if survivors.len() == num_select {
+            break;
+        }
+    }
+
+    survivors
+}
+
+// binary tournaments by the crowded comparison: the lower front wins, and within
+// a front the less crowded individual wins
+pub fn crowded_tournament_indices<R: Rng>(ranks: &[usize], distances: &[f64], num_select: usize, rng: &mut R) -> Vec<usize> {
+    let num_inds = ranks.len();
+
+    let mut selected = Vec::with_capacity(num_select);
+    while selected.len() < num_select {
+        let index = rng.gen_range(0, num_inds);
+        let other = rng.gen_range(0, num_inds);
+
+        let winner = if ranks[other] < ranks[index] ||
+                        (ranks[other] == ranks[index] && distances[other] > distances[index]) {
+            other
+        } else {
+            index
+        };
+
+        selected.push(winner);
+    }
+
+    selected
+}
+
+// fill new_pop with parents chosen by crowded tournaments on the given objectives
+pub fn nsga2_selection<R: Rng, T: Copy>(pop: &Pop<T>, new_pop: &mut Pop<T>, objectives: &[Vec<f64>], rng: &mut R) {
+    let (ranks, distances) = rank_and_crowding(objectives);
+    let selected = crowded_tournament_indices(&ranks, &distances, new_pop.0.len(), rng);
+    copy_selected(pop, new_pop, &selected);
+}
+
+pub struct NsgaState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub alt_population: Rc<RefCell<Pop<T>>>,
+    pub objectives: Rc<RefCell<Vec<Vec<f64>>>>,
+}
+
+impl<T> NsgaState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               alt_population: Rc<RefCell<Pop<T>>>,
+               objectives: Rc<RefCell<Vec<Vec<f64>>>>) -> NsgaState<T> {
+        NsgaState {
+            population,
+            alt_population,
+            objectives,
+        }
+    }
+}
+
+pub fn nsga2_stage<S, T, R>(getter: Getter<S, NsgaState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: Copy + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let nsga_state = getter(state);
+        nsga2_selection(&nsga_state.population.borrow(),
+                        &mut nsga_state.alt_population.borrow_mut(),
+                        &nsga_state.objectives.borrow(),
+                        rng);
+        nsga_state.population.swap(&nsga_state.alt_population);
+    });
+
+    f
+}
+
+#[test]
+fn test_non_dominated_sort() {
+    let objectives = vec!(vec!(1.0, 4.0),
+                          vec!(2.0, 2.0),
+                          vec!(4.0, 1.0),
+                          vec!(3.0, 3.0),
+                          vec!(5.0, 5.0));
+
+    assert!(dominates(&objectives[1], &objectives[3]));
+    assert!(!dominates(&objectives[0], &objectives[1]));
+    assert!(!dominates(&objectives[0], &objectives[0]));
+
+    let fronts = non_dominated_sort(&objectives);
+    assert_eq!(fronts, vec!(vec!(0, 1, 2), vec!(3), vec!(4)));
+
+    // the extremes are kept ahead of the middle of the front
+    let distances = crowding_distance(&objectives, &fronts[0]);
+    assert_eq!(distances[0], f64::INFINITY);
+    assert_eq!(distances[2], f64::INFINITY);
+    assert!(distances[1] < f64::INFINITY);
+
+    let mut survivors = nsga2_survivors(&objectives, 2);
+    survivors.sort();
+    assert_eq!(survivors, vec!(0, 2));
+
+    assert_eq!(nsga2_survivors(&objectives, 4).len(), 4);
+}
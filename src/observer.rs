@@ -0,0 +1,60 @@
+use types::*;
+use termination::*;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+// Hooks called during a run. Every method has a default that does nothing,
+// so an observer only implements the events it cares about.
+pub trait Observer<T> {
+    fn on_start(&mut self) {
+    }
+
+    // called after each generation has been evaluated, before selection.
+    // Returning Control::Stop ends the run with StopReason::Observer.
+    fn on_generation(&mut self, _generation: usize, _pop: &Pop<T>, _fitnesses: &Vec<f64>) -> Control {
+        Control::Continue
+    }
+
+    fn on_new_best(&mut self, _generation: usize, _ind: &Ind<T>, _fitness: f64) {
+    }
+
+    fn on_end(&mut self, _stop_reason: StopReason, _best: &Ind<T>, _best_fitness: f64) {
+    }
+}
+
+pub struct NullObserver;
+
+impl<T> Observer<T> for NullObserver {
+}
+
+// prints the best and average fitness every print_every generations
+pub struct PrintObserver {
+    pub print_every: usize,
+}
+
+impl PrintObserver {
+    pub fn new(print_every: usize) -> PrintObserver {
+        PrintObserver { print_every }
+    }
+}
+
+impl<T> Observer<T> for PrintObserver {
+    fn on_generation(&mut self, generation: usize, _pop: &Pop<T>, fitnesses: &Vec<f64>) -> Control {
+        if self.print_every > 0 && generation % self.print_every == 0 {
+            let best = fitnesses.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let average = fitnesses.iter().sum::<f64>() / fitnesses.len() as f64;
+            println!("generation {}: best = {}, average = {}", generation, best, average);
+        }
+
+        Control::Continue
+    }
+
+    fn on_end(&mut self, stop_reason: StopReason, _best: &Ind<T>, best_fitness: f64) {
+        println!("stopped by {:?}, best fitness = {}", stop_reason, best_fitness);
+    }
+}
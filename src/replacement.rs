@@ -0,0 +1,280 @@
+use rand::prelude::*;
+
+use num::PrimInt;
+
+use types::*;
+use selection::*;
+use niching::*;
+
+
+// Chooses which member of the population an offspring replaces in a
+// steady-state run, from rgep_steady_state or ga_steady_state.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ReplacementPolicy {
+    #[default]
+    Worst,
+    Random,
+    InverseTournament(usize),
+    // the child competes with the closest of this many random individuals,
+    // replacing it only if the child is fitter
+    RestrictedTournament(usize),
+    // each child competes with the closer of its two parents, replacing it if the
+    // child is at least as fit
+    DeterministicCrowding,
+}
+
+// Pick the index of an individual to replace. Indices in protected (such as elites)
+// are never chosen, so protected must be smaller than the population. The crowding
+// policies depend on the offspring themselves, so they give None here and are
+// handled by replace_offspring.
+pub fn replacement_index<R: Rng>(policy: ReplacementPolicy, fitnesses: &[f64], protected: &[usize], rng: &mut R) -> Option<usize> {
+    let num_inds = fitnesses.len();
+    assert!(protected.len() < num_inds, "Cannot replace an individual when every individual is protected!");
+
+    match policy {
+        ReplacementPolicy::Worst => {
+            let mut worst_index = None;
+            for index in 0..num_inds {
+                if protected.contains(&index) {
+                    continue;
+                }
+
+                match worst_index {
+                    Some(worst) if fitnesses[worst] <= fitnesses[index] => {},
+                    _ => worst_index = Some(index),
+                }
+            }
+            worst_index
+        },
+
+        ReplacementPolicy::Random => {
+            Some(random_unprotected(num_inds, protected, rng))
+        },
+
+        ReplacementPolicy::InverseTournament(tourn_size) => {
+            let mut worst_index = random_unprotected(num_inds, protected, rng);
+            for _ in 1..tourn_size {
+                let index = random_unprotected(num_inds, protected, rng);
+                if fitnesses[index] < fitnesses[worst_index] {
+                    worst_index = index;
+                }
+            }
+            Some(worst_index)
+        },
+
+        ReplacementPolicy::RestrictedTournament(_) | ReplacementPolicy::DeterministicCrowding => {
+            None
+        },
+    }
+}
+
+// A replacement policy, and the number of fittest individuals protected from the
+// fitness based policies. The crowding policies never replace an individual with a
+// less fit one, so they need no protection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplacementParams {
+    pub policy: ReplacementPolicy,
+    pub elitism: usize,
+    // the number of bits used in each word, for the crowding distances
+    pub bits_used: usize,
+}
+
+// Place offspring into a steady-state population, draining them. parents[index] is the index of
+// the individual that offspring.0[index] was copied from, and consecutive pairs of offspring
+// are assumed to have been crossed with each other. Returns the (offspring index, population
+// index) of each offspring that entered the population.
+pub fn replace_offspring<T, R>(replacement: ReplacementParams,
+                               pop: &mut Pop<T>,
+                               fitnesses: &mut [f64],
+                               offspring: &mut Pop<T>,
+                               offspring_fitnesses: &[f64],
+                               parents: &[usize],
+                               rng: &mut R) -> Vec<(usize, usize)>
+    where T: PrimInt,
+          R: Rng {
+    let mut placed = Vec::with_capacity(offspring.0.len());
+
+    match replacement.policy {
+        ReplacementPolicy::RestrictedTournament(window) => {
+            for (child_index, (child, fitness)) in offspring.0.drain(..).zip(offspring_fitnesses.iter()).enumerate() {
+                let index = restricted_tournament_index(pop, &child, window, replacement.bits_used, rng);
+                if *fitness > fitnesses[index] {
+                    pop.0[index] = child;
+                    fitnesses[index] = *fitness;
+                    placed.push((child_index, index));
+                }
+            }
+        },
+
+        ReplacementPolicy::DeterministicCrowding => {
+            let mut competitions = Vec::with_capacity(offspring.0.len());
+            let mut index = 0;
+            while index < offspring.0.len() {
+                if index + 1 < offspring.0.len() {
+                    let pairs = crowding_pairs((&pop.0[parents[index]], &pop.0[parents[index + 1]]),
+                                               (&offspring.0[index], &offspring.0[index + 1]),
+                                               replacement.bits_used);
+                    for (parent, child) in pairs.iter() {
+                        competitions.push((parents[index + parent], index + child));
+                    }
+                    index += 2;
+                } else {
+                    competitions.push((parents[index], index));
+                    index += 1;
+                }
+            }
+
+            for (parent_index, child_index) in competitions {
+                if offspring_fitnesses[child_index] >= fitnesses[parent_index] {
+                    pop.0[parent_index] = offspring.0[child_index].clone();
+                    fitnesses[parent_index] = offspring_fitnesses[child_index];
+                    placed.push((child_index, parent_index));
+                }
+            }
+            offspring.0.clear();
+        },
+
+        _ => {
+            for (child_index, (child, fitness)) in offspring.0.drain(..).zip(offspring_fitnesses.iter()).enumerate() {
+                let elite_indices = k_elite(fitnesses, replacement.elitism);
+                if let Some(index) = replacement_index(replacement.policy, fitnesses, &elite_indices, rng) {
+                    pop.0[index] = child;
+                    fitnesses[index] = *fitness;
+                    placed.push((child_index, index));
+                }
+            }
+        },
+    }
+
+    placed
+}
+
+// How a generational run forms its next population. Generational runs replace the whole
+// population with the selected and varied individuals. The evolution strategy schemes
+// select lambda parents from the mu = pop_size individuals, vary and evaluate them, and
+// keep the mu fittest of the offspring (Comma) or of the parents and offspring (Plus).
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Survival {
+    #[default]
+    Generational,
+    Comma { lambda: usize },
+    Plus { lambda: usize },
+}
+
+impl Survival {
+    // the number of offspring produced each generation from a population of mu
+    pub fn num_offspring(&self, mu: usize) -> usize {
+        match self {
+            Survival::Generational => mu,
+            Survival::Comma { lambda } => {
+                assert!(*lambda >= mu, "(mu, lambda) survival needs lambda ({}) to be at least mu ({})", lambda, mu);
+                *lambda
+            },
+            Survival::Plus { lambda } => *lambda,
+        }
+    }
+}
+
+// Truncation survival for the evolution strategy schemes. The population and its fitnesses
+// are replaced by the fittest of the offspring, or of the population and offspring together.
+// Generational survival is treated as Comma. Returns the indices of the survivors among the
+// candidates, which are the population followed by the offspring for Plus, and otherwise
+// the offspring alone.
+pub fn survive<T: Clone>(survival: Survival,
+                         pop: &mut Pop<T>,
+                         fitnesses: &mut [f64],
+                         offspring: &Pop<T>,
+                         offspring_fitnesses: &[f64]) -> Vec<usize> {
+    let mu = pop.0.len();
+
+    let (candidates, candidate_fitnesses) = match survival {
+        Survival::Plus { .. } => {
+            let mut candidates = pop.clone();
+            candidates.0.extend(offspring.0.iter().cloned());
+            let mut candidate_fitnesses = fitnesses.to_vec();
+            candidate_fitnesses.extend(offspring_fitnesses.iter());
+            (candidates, candidate_fitnesses)
+        },
+
+        _ => (offspring.clone(), offspring_fitnesses.to_vec()),
+    };
+
+    let survivors = k_elite(&candidate_fitnesses, mu);
+    for (index, survivor) in survivors.iter().enumerate() {
+        pop.0[index] = candidates.0[*survivor].clone();
+        fitnesses[index] = candidate_fitnesses[*survivor];
+    }
+
+    survivors
+}
+
+fn random_unprotected<R: Rng>(num_inds: usize, protected: &[usize], rng: &mut R) -> usize {
+    loop {
+        let index = rng.gen_range(0, num_inds);
+        if !protected.contains(&index) {
+            return index;
+        }
+    }
+}
+
+#[test]
+fn test_replacement_index_skips_protected() {
+    let fitnesses = vec!(3.0, 0.5, 2.0, 1.0);
+    let mut rng = thread_rng();
+
+    assert_eq!(replacement_index(ReplacementPolicy::Worst, &fitnesses, &[], &mut rng), Some(1));
+    assert_eq!(replacement_index(ReplacementPolicy::Worst, &fitnesses, &[1], &mut rng), Some(3));
+
+    for _ in 0..100 {
+        let index = replacement_index(ReplacementPolicy::InverseTournament(2), &fitnesses, &[0, 1], &mut rng);
+        assert!(index == Some(2) || index == Some(3));
+    }
+
+    assert_eq!(replacement_index(ReplacementPolicy::DeterministicCrowding, &fitnesses, &[], &mut rng), None);
+}
+
+#[test]
+fn test_crowding_replacement() {
+    let mut rng = thread_rng();
+
+    let mut pop = Pop(vec!(Ind(vec!(0x00u8)), Ind(vec!(0xFFu8)), Ind(vec!(0x0Fu8))));
+    let mut fitnesses = vec!(1.0, 2.0, 3.0);
+
+    // the first child is closest to parent 1 and fitter, and the second is closest
+    // to parent 0 but less fit
+    let mut offspring = Pop(vec!(Ind(vec!(0xFEu8)), Ind(vec!(0x01u8))));
+    let replacement = ReplacementParams { policy: ReplacementPolicy::DeterministicCrowding, elitism: 0, bits_used: 8 };
+    let placed = replace_offspring(replacement, &mut pop, &mut fitnesses, &mut offspring, &vec!(5.0, 0.5), &[0, 1], &mut rng);
+    assert_eq!(placed, vec!((0, 1)));
+    assert_eq!(pop, Pop(vec!(Ind(vec!(0x00u8)), Ind(vec!(0xFEu8)), Ind(vec!(0x0Fu8)))));
+    assert_eq!(fitnesses, vec!(1.0, 5.0, 3.0));
+    assert!(offspring.0.is_empty());
+
+    // with a window covering the population, the child competes with its closest individual
+    let mut offspring = Pop(vec!(Ind(vec!(0x0Eu8))));
+    let replacement = ReplacementParams { policy: ReplacementPolicy::RestrictedTournament(100), elitism: 0, bits_used: 8 };
+    let placed = replace_offspring(replacement, &mut pop, &mut fitnesses, &mut offspring, &vec!(4.0), &[0], &mut rng);
+    assert_eq!(placed, vec!((0, 2)));
+    assert_eq!(pop.0[2], Ind(vec!(0x0Eu8)));
+    assert_eq!(fitnesses, vec!(1.0, 5.0, 4.0));
+}
+
+#[test]
+fn test_survival_truncates() {
+    let offspring = Pop(vec!(Ind(vec!(10)), Ind(vec!(11)), Ind(vec!(12))));
+    let offspring_fitnesses = vec!(1.0, 5.0, 3.0);
+
+    let mut pop = Pop(vec!(Ind(vec!(0)), Ind(vec!(1))));
+    let mut fitnesses = vec!(4.0, 0.0);
+    survive(Survival::Comma { lambda: 3 }, &mut pop, &mut fitnesses, &offspring, &offspring_fitnesses);
+    assert_eq!(pop, Pop(vec!(Ind(vec!(11)), Ind(vec!(12)))));
+    assert_eq!(fitnesses, vec!(5.0, 3.0));
+
+    let mut pop = Pop(vec!(Ind(vec!(0)), Ind(vec!(1))));
+    let mut fitnesses = vec!(4.0, 0.0);
+    survive(Survival::Plus { lambda: 3 }, &mut pop, &mut fitnesses, &offspring, &offspring_fitnesses);
+    assert_eq!(pop, Pop(vec!(Ind(vec!(11)), Ind(vec!(0)))));
+    assert_eq!(fitnesses, vec!(5.0, 4.0));
+
+    assert_eq!(Survival::Plus { lambda: 1 }.num_offspring(2), 1);
+}
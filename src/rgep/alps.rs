@@ -0,0 +1,413 @@
+use rand::prelude::*;
+
+use crate::types::*;
+use crate::selection::*;
+use crate::evaluation::*;
+use crate::termination::*;
+use crate::observer::*;
+use crate::hall_of_fame::*;
+use crate::alps::*;
+use crate::adaptive::*;
+
+use rgep::*;
+use rgep::context::*;
+
+
+// the programs' context, and the state and function they are evaluated with
+struct LayerEvaluator<'a, R, A: Clone + 'static, B: Clone + 'static> {
+    context: &'a Context<A, B>,
+    state: &'a B,
+    eval_ind: &'a EvalFunction<A, B, R>,
+}
+
+impl<'a, R, A, B> LayerEvaluator<'a, R, A, B>
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    fn evaluate(&self, params: &RgepParams, pop: &PopU8, rng: &mut R) -> Vec<f64> {
+        let mut fitnesses = rgep_evaluate(pop, self.context, self.state, self.eval_ind, rng);
+        params.objective.convert(&mut fitnesses);
+        fitnesses
+    }
+}
+
+// a layer of params.pop_size fresh individuals from create_rgep, with age 0
+fn fresh_layer<R, A, B>(params: &RgepParams,
+                        evaluator: &LayerEvaluator<R, A, B>,
+                        rng: &mut R) -> AlpsLayer<u8>
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    let pop = create_rgep(params, evaluator.context, rng);
+    let fitnesses = evaluator.evaluate(params, &pop, rng);
+
+    AlpsLayer {
+        ages: vec![0; pop.0.len()],
+        rates: rgep_mutation_rates(params, pop.0.len()).rates,
+        pop,
+        fitnesses,
+    }
+}
+
+// Breed the next generation of a layer from the layer and the layer below it. Only
+// individuals young enough for the layer can be parents, unless there are none. Parents
+// are selected after niching and scaling their fitnesses, as in rgep. Also returns how
+// many offspring are fitter than their parents, for MutationRate::OneFifth.
+fn breed_layer<R, A, B>(params: &RgepParams,
+                        rates: &MutationRates,
+                        age_limit: Option<usize>,
+                        layers: &[&AlpsLayer<u8>],
+                        evaluator: &LayerEvaluator<R, A, B>,
+                        rng: &mut R) -> (AlpsLayer<u8>, usize)
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    let mut pool = AlpsLayer::new();
+    for layer in layers.iter() {
+        for index in 0..layer.len() {
+            if age_limit.map_or(true, |limit| layer.ages[index] < limit) {
+                pool.pop.0.push(layer.pop.0[index].clone());
+                pool.ages.push(layer.ages[index]);
+                pool.fitnesses.push(layer.fitnesses[index]);
+                if !layer.rates.is_empty() {
+                    pool.rates.push(layer.rates[index]);
+                }
+            }
+        }
+    }
+
+    if pool.is_empty() {
+        return breed_layer(params, rates, None, layers, evaluator, rng);
+    }
+
+    let mut selection_fitnesses = pool.fitnesses.clone();
+    params.niching.apply(&pool.pop, evaluator.context.bits_per_sym(), &mut selection_fitnesses);
+    params.scaling.apply(&mut selection_fitnesses);
+    let selected = select_indices(params.selection, &selection_fitnesses, params.pop_size, params.elitism, rng);
+
+    let mut pop = create_rgep_fast(params);
+    copy_selected(&pool.pop, &mut pop, &selected);
+    let parent_fitnesses = selected_fitnesses(&pool.fitnesses, &selected);
+    let mut offspring_rates = rates.clone();
+    offspring_rates.rates = pool.rates.clone();
+    offspring_rates.select(&selected);
+    let order = rgep_vary(&mut pop, &parent_fitnesses, &mut offspring_rates, params, evaluator.context.bits_per_sym(), rng);
+
+    let fitnesses = evaluator.evaluate(params, &pop, rng);
+    let successes = count_successes(&fitnesses, &selected_fitnesses(&parent_fitnesses, &order));
+
+    let layer = AlpsLayer {
+        ages: offspring_ages(&pool.ages, &order.iter().map(|index| selected[*index]).collect::<Vec<usize>>()),
+        rates: offspring_rates.rates,
+        pop,
+        fitnesses,
+    };
+
+    (layer, successes)
+}
+
+// Age-layered RGEP (ALPS). The population is split into layers of params.pop_size
+// individuals, each only holding individuals up to its layer's age limit. Each layer
+// breeds from itself and the layer below with params.selection, so individuals that
+// grow too old for a layer move up. Every alps.age_gap generations the bottom layer is
+// replaced with fresh individuals. Layers are opened one at a time, as the run becomes
+// old enough to fill them. The result's population holds every layer, bottom first.
+pub fn rgep_alps<R, A, B>(params: &RgepParams,
+                          alps: &AlpsParams,
+                          context: &Context<A, B>,
+                          state: &B,
+                          eval_ind: &EvalFunction<A, B, R>) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    rgep_alps_with_observer(params, alps, context, state, eval_ind, &mut NullObserver)
+}
+
+pub fn rgep_alps_with_observer<R, A, B>(params: &RgepParams,
+                                        alps: &AlpsParams,
+                                        context: &Context<A, B>,
+                                        state: &B,
+                                        eval_ind: &EvalFunction<A, B, R>,
+                                        observer: &mut dyn Observer<u8>) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    assert!(alps.num_layers > 0 && alps.age_gap > 0, "ALPS needs at least one layer and an age gap of at least one!");
+    assert!(params.hall_of_fame_elites == 0, "Hall of fame elites are only reinserted by generational runs, not ALPS runs!");
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
+    let evaluator = LayerEvaluator { context, state, eval_ind };
+    let mut layers = vec!(fresh_layer(params, &evaluator, rng));
+
+    let mut best = layers[0].pop.0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
+    let mut rates = rgep_mutation_rates(params, 0);
+
+    let mut progress = Progress::new();
+    progress.evaluations += layers[0].len();
+
+    let mut pop = Pop(Vec::new());
+    let mut fitnesses = Vec::new();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        let generation = progress.generation;
+        rates.start_generation(generation, params.num_gens);
+
+        let top = layers.len() - 1;
+        if layers.len() < alps.num_layers && alps.age_limit(top).map_or(false, |limit| generation >= limit) {
+            layers.push(AlpsLayer::new());
+        }
+
+        // successes are counted over the bred layers, as fresh individuals have no parents
+        let mut successes = 0;
+        let mut num_offspring = 0;
+        let mut new_layers = Vec::with_capacity(layers.len());
+        for layer_index in 0..layers.len() {
+            let new_layer = if layer_index == 0 && generation > 0 && generation % alps.age_gap == 0 {
+                fresh_layer(params, &evaluator, rng)
+            } else {
+                let parent_layers: &[&AlpsLayer<u8>] = if layer_index == 0 {
+                    &[&layers[0]]
+                } else {
+                    &[&layers[layer_index], &layers[layer_index - 1]]
+                };
+                let (new_layer, layer_successes) =
+                    breed_layer(params, &rates, alps.age_limit(layer_index), parent_layers, &evaluator, rng);
+                successes += layer_successes;
+                num_offspring += new_layer.len();
+                new_layer
+            };
+            new_layers.push(new_layer);
+        }
+        layers = new_layers;
+        rates.record_successes(successes, num_offspring);
+
+        pop.0.clear();
+        fitnesses.clear();
+        for layer in layers.iter() {
+            pop.0.extend(layer.pop.0.iter().cloned());
+            fitnesses.extend(layer.fitnesses.iter());
+        }
+
+        let index_fittest = fittest(&fitnesses);
+        fitness_history.push(fitnesses[index_fittest]);
+        let improved = progress.update(fitnesses[index_fittest], fitnesses.len());
+        hall_of_fame.update(&pop, &fitnesses, generation);
+
+        let control = observer.on_generation(generation, &pop, &fitnesses);
+        if improved {
+            best = pop.0[index_fittest].clone();
+            observer.on_new_best(generation, &best, progress.best_fitness);
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    if pop.0.is_empty() {
+        pop = layers[0].pop.clone();
+    }
+
+    RgepResult {
+        best_string: context.to_string(&best),
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        population: pop,
+        hall_of_fame,
+    }
+}
+
+#[test]
+fn test_rgep_alps_opens_layers() {
+    let context = arith_context();
+
+    let params = RgepParams {
+        pop_size: 10,
+        ind_size: 10,
+        num_gens: 30,
+        seed: 11,
+        ..RgepParams::default()
+    };
+
+    let alps = AlpsParams { num_layers: 3, age_gap: 4, aging: AgingScheme::Linear };
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    let result = rgep_alps(&params, &alps, &context, &(), eval_prog);
+
+    // layers open at generations 4 and 8, so the run ends with all three
+    assert_eq!(result.population.0.len(), 3 * params.pop_size);
+    assert_eq!(result.fitness_history.len(), params.num_gens);
+    assert!(result.fitness_history.iter().all(|fitness| *fitness <= result.best_fitness));
+
+}
+
+#[test]
+fn test_rgep_alps_ages_follow_mates() {
+    let context = arith_context();
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+    let evaluator = LayerEvaluator { context: &context, state: &(), eval_ind: eval_prog };
+
+    // without crossover, and with too little mutation to happen, each offspring is a copy
+    // of its distinct parent
+    let mut layer = AlpsLayer::new();
+    for index in 0..10 {
+        layer.pop.0.push(Ind((0..4).map(|sym| ((index >> (2 * sym)) & 3) as u8).collect()));
+        layer.ages.push(index);
+        layer.fitnesses.push(1.0);
+    }
+
+    for mating in vec!(Mating::Shuffled, Mating::Tournament { size: 2 }) {
+        let params = RgepParams {
+            pop_size: 10,
+            ind_size: 4,
+            prob_mut: 1e-12,
+            prob_one_point_crossover: 0.0,
+            prob_two_point_crossover: 0.0,
+            prob_rotation: 0.0,
+            elitism: 0,
+            mating,
+            ..RgepParams::default()
+        };
+        let rates = rgep_mutation_rates(&params, 0);
+        let mut rng = R::seed_from_u64(0);
+
+        let (new_layer, _) = breed_layer(&params, &rates, None, &[&layer], &evaluator, &mut rng);
+
+        // each pair of mates takes the age of the older, plus one
+        for (pair, ages) in new_layer.pop.0.chunks(2).zip(new_layer.ages.chunks(2)) {
+            let parent_age = |ind: &IndU8| layer.pop.0.iter().position(|parent| parent == ind).unwrap();
+            let age = pair.iter().map(parent_age).max().unwrap() + 1;
+            assert!(ages.iter().all(|child_age| *child_age == age));
+        }
+    }
+}
+
+#[test]
+fn test_rgep_alps_scales_fitnesses() {
+    let context = arith_context();
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+    let evaluator = LayerEvaluator { context: &context, state: &(), eval_ind: eval_prog };
+
+    // each offspring is a copy of its distinct parent. The fitnesses are all negative, so
+    // only once window scaling is applied do the others have a chance against the fittest,
+    // while the least fit, index 0, has none
+    let mut layer = AlpsLayer::new();
+    for index in 0..10 {
+        layer.pop.0.push(Ind((0..4).map(|sym| ((index >> (2 * sym)) & 3) as u8).collect()));
+        layer.ages.push(0);
+        layer.fitnesses.push(index as f64 - 20.0);
+    }
+
+    let params = RgepParams {
+        pop_size: 10,
+        ind_size: 4,
+        prob_mut: 1e-12,
+        prob_one_point_crossover: 0.0,
+        prob_two_point_crossover: 0.0,
+        prob_rotation: 0.0,
+        elitism: 0,
+        selection: Selection::StochasticUniversal,
+        scaling: FitnessScaling::Window,
+        ..RgepParams::default()
+    };
+    let rates = rgep_mutation_rates(&params, 0);
+
+    for seed in 0..10 {
+        let mut rng = R::seed_from_u64(seed);
+        let (new_layer, _) = breed_layer(&params, &rates, None, &[&layer], &evaluator, &mut rng);
+        assert!(new_layer.pop.0.iter().all(|ind| *ind != layer.pop.0[0]));
+        assert!(new_layer.pop.0.iter().any(|ind| *ind != layer.pop.0[9]));
+    }
+}
+
+#[test]
+fn test_rgep_alps_inherits_rates() {
+    let context = arith_context();
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+    let evaluator = LayerEvaluator { context: &context, state: &(), eval_ind: eval_prog };
+
+    // with a learning rate of 0 and rates too low for mutation to happen, each offspring
+    // is a copy of its distinct parent, with its parent's rate
+    let mut layer = AlpsLayer::new();
+    for index in 0..10 {
+        layer.pop.0.push(Ind((0..4).map(|sym| ((index >> (2 * sym)) & 3) as u8).collect()));
+        layer.ages.push(0);
+        layer.fitnesses.push(1.0);
+        layer.rates.push(MIN_MUTATION_RATE * (index + 1) as f64);
+    }
+
+    let params = RgepParams {
+        pop_size: 10,
+        ind_size: 4,
+        prob_mut: MIN_MUTATION_RATE,
+        mutation_rate: MutationRate::SelfAdaptive { learning_rate: 0.0 },
+        prob_one_point_crossover: 0.0,
+        prob_two_point_crossover: 0.0,
+        prob_rotation: 0.0,
+        elitism: 0,
+        mating: Mating::Shuffled,
+        ..RgepParams::default()
+    };
+    let rates = rgep_mutation_rates(&params, 0);
+    let mut rng = R::seed_from_u64(0);
+
+    let (new_layer, _) = breed_layer(&params, &rates, None, &[&layer], &evaluator, &mut rng);
+
+    assert_eq!(new_layer.rates.len(), params.pop_size);
+    for (ind, rate) in new_layer.pop.0.iter().zip(new_layer.rates.iter()) {
+        let parent = layer.pop.0.iter().position(|parent| parent == ind).unwrap();
+        assert_eq!(*rate, layer.rates[parent]);
+    }
+
+    assert_eq!(fresh_layer(&params, &evaluator, &mut rng).rates, vec![params.prob_mut; params.pop_size]);
+}
+
+#[test]
+fn test_rgep_alps_counts_successes() {
+    let context = arith_context();
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+    let evaluator = LayerEvaluator { context: &context, state: &(), eval_ind: eval_prog };
+
+    // parents are chosen uniformly, and each offspring is a copy of its parent, shuffled
+    // away from its parent's position. Every other parent's recorded fitness is a little
+    // below its own, so only copies of those are successes.
+    let params = RgepParams {
+        pop_size: 10,
+        ind_size: 10,
+        prob_mut: 1e-12,
+        prob_one_point_crossover: 0.0,
+        prob_two_point_crossover: 0.0,
+        prob_rotation: 0.0,
+        elitism: 0,
+        selection: Selection::Tournament { size: 1, prob: 1.0 },
+        mutation_rate: MutationRate::OneFifth { factor: 1.5 },
+        mating: Mating::Shuffled,
+        ..RgepParams::default()
+    };
+    let rates = rgep_mutation_rates(&params, 0);
+
+    let mut rng = R::seed_from_u64(0);
+    let mut layer = fresh_layer(&params, &evaluator, &mut rng);
+    for (index, fitness) in layer.fitnesses.iter_mut().enumerate() {
+        *fitness += if index % 2 == 0 { 0.01 } else { -0.01 };
+    }
+
+    for seed in 0..10 {
+        let mut rng = R::seed_from_u64(seed);
+        let (new_layer, successes) = breed_layer(&params, &rates, None, &[&layer], &evaluator, &mut rng);
+
+        let expected = new_layer.pop.0.iter().zip(new_layer.fitnesses.iter()).filter(|(ind, fitness)| {
+            let parent = layer.pop.0.iter().position(|parent| parent == *ind).unwrap();
+            **fitness > layer.fitnesses[parent]
+        }).count();
+        assert_eq!(successes, expected);
+    }
+}
@@ -0,0 +1,170 @@
+use rand::prelude::*;
+
+use crate::types::*;
+use crate::selection::*;
+use crate::evaluation::*;
+use crate::termination::*;
+use crate::observer::*;
+use crate::lexicase::*;
+use crate::hall_of_fame::*;
+use crate::adaptive::*;
+use crate::replacement::*;
+
+use rgep::*;
+use rgep::context::*;
+
+
+// An evaluation function returning the program's error on each fitness case.
+// Every program must return the same number of errors, in the same case order.
+pub type CaseEvalFunction<A, B, R> = dyn Fn(&Program<A, B>, &mut B, &mut R) -> Vec<f64>;
+
+// the per-case errors of each individual, using the same evaluation streams as rgep_evaluate
+pub fn rgep_evaluate_cases<R, A, B>(pop: &PopU8,
+                                    context: &Context<A, B>,
+                                    state: &B,
+                                    eval_cases: &CaseEvalFunction<A, B, R>,
+                                    rng: &mut R) -> Vec<Vec<f64>>
+    where R: Rng + SeedableRng,
+          A: Clone,
+          B: Clone {
+    let mut errors = Vec::with_capacity(pop.0.len());
+
+    let mut prog = Program(Vec::with_capacity(pop.0[0].0.len()));
+
+    let gen_seed: u64 = rng.gen();
+    for (index, ind) in pop.0.iter().enumerate() {
+        let mut local_state = state.clone();
+        let mut eval_rng = R::seed_from_u64(eval_seed(gen_seed, index));
+        context.compile_to(ind, &mut prog);
+        errors.push(eval_cases(&prog, &mut local_state, &mut eval_rng));
+    }
+
+    errors
+}
+
+// RGEP with lexicase selection over per-case errors in place of params.selection.
+// The fitness reported for each individual is 1 / (1 + total error).
+pub fn rgep_lexicase<R, A, B>(params: &RgepParams,
+                              context: &Context<A, B>,
+                              state: &B,
+                              eval_cases: &CaseEvalFunction<A, B, R>,
+                              lexicase: Lexicase) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    rgep_lexicase_with_observer(params, context, state, eval_cases, lexicase, &mut NullObserver)
+}
+
+pub fn rgep_lexicase_with_observer<R, A, B>(params: &RgepParams,
+                                            context: &Context<A, B>,
+                                            state: &B,
+                                            eval_cases: &CaseEvalFunction<A, B, R>,
+                                            lexicase: Lexicase,
+                                            observer: &mut dyn Observer<u8>) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    assert!(params.survival == Survival::Generational, "Lexicase runs are generational, and do not support (mu, lambda) or (mu + lambda) survival!");
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
+    let mut pop = create_rgep(params, context, rng);
+    let mut alt_pop = create_rgep_fast(params);
+
+    let bits_per_sym = context.bits_per_sym();
+
+    let mut best = pop.0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
+
+    // the fitnesses of the individuals each was selected from, for mating and for
+    // counting successful offspring
+    let mut parent_fitnesses = vec![0.0; params.pop_size];
+    let mut rates = rgep_mutation_rates(params, params.pop_size);
+
+    let mut progress = Progress::new();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        let generation = progress.generation;
+        rates.start_generation(generation, params.num_gens);
+        let order = rgep_vary(&mut pop, &parent_fitnesses, &mut rates, params, bits_per_sym, rng);
+        let mut offspring_parent_fitnesses = selected_fitnesses(&parent_fitnesses, &order);
+        rgep_reinsert(&hall_of_fame, &mut pop, &mut offspring_parent_fitnesses, &mut rates, params.hall_of_fame_elites);
+
+        let errors = rgep_evaluate_cases(&pop, context, state, eval_cases, rng);
+        let fitnesses = case_fitnesses(&errors);
+
+        if generation > 0 {
+            rates.record_successes(count_successes(&fitnesses, &offspring_parent_fitnesses), fitnesses.len());
+        }
+
+        let index_fittest = fittest(&fitnesses);
+        fitness_history.push(fitnesses[index_fittest]);
+        let improved = progress.update(fitnesses[index_fittest], fitnesses.len());
+        hall_of_fame.update(&pop, &fitnesses, generation);
+
+        let control = observer.on_generation(generation, &pop, &fitnesses);
+        if improved {
+            best = pop.0[index_fittest].clone();
+            observer.on_new_best(generation, &best, progress.best_fitness);
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+
+        let selected = lexicase_indices(&errors, lexicase, params.elitism, rng);
+        copy_selected(&pop, &mut alt_pop, &selected);
+        parent_fitnesses = selected_fitnesses(&fitnesses, &selected);
+        rates.select(&selected);
+
+        std::mem::swap(&mut pop, &mut alt_pop);
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    RgepResult {
+        best_string: context.to_string(&best),
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        population: pop,
+        hall_of_fame,
+    }
+}
+
+#[test]
+fn test_rgep_lexicase() {
+    use domains::symbols::*;
+
+    let context = arith_context();
+
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        num_gens: 20,
+        seed: 5,
+        ..RgepParams::default()
+    };
+
+    // errors against three targets, which no single program can hit together
+    let eval_cases: &CaseEvalFunction<f64, (), R> =
+        &|prog: &Program<f64, ()>, state: &mut (), _rng: &mut R| -> Vec<f64> {
+            let value = prog.eval(state, 0.0);
+            vec!((value - 3.0).abs(), (value - 4.0).abs(), (value - 5.0).abs())
+        };
+
+    for lexicase in vec!(Lexicase::Standard, Lexicase::Epsilon, Lexicase::DownSampled { fraction: 0.5 }) {
+        let result = rgep_lexicase(&params, &context, &(), eval_cases, lexicase);
+        assert_eq!(result.fitness_history.len(), params.num_gens);
+        assert_eq!(result.population.0.len(), params.pop_size);
+        assert!(result.best_fitness > 0.0 && result.best_fitness <= 1.0);
+    }
+}
@@ -0,0 +1,168 @@
+use rand::prelude::*;
+
+use crate::types::*;
+use crate::selection::*;
+use crate::evaluation::*;
+use crate::termination::*;
+use crate::hall_of_fame::*;
+use crate::adaptive::*;
+use crate::replacement::*;
+
+use domains::symbols::*;
+
+use rgep::*;
+use rgep::context::*;
+
+
+// Ask/tell interface to generational RGEP, for fitness values computed outside
+// of an EvalFunction. Each generation the caller asks for the individuals (or
+// their programs), evaluates them however it likes, and tells the engine the
+// fitnesses. Telling runs selection and variation to produce the next generation.
+pub struct RgepEngine<R> {
+    params: RgepParams,
+    bits_per_sym: usize,
+
+    pop: PopU8,
+    alt_pop: PopU8,
+
+    best: IndU8,
+    fitness_history: Vec<f64>,
+    progress: Progress,
+    hall_of_fame: HallOfFame<u8>,
+
+    // the mutation rates, and the fitness of the parent of each individual asked for, which
+    // is empty before the first generation is varied
+    rates: MutationRates,
+    parent_fitnesses: Vec<f64>,
+
+    rng: R,
+}
+
+impl<R: Rng + SeedableRng> RgepEngine<R> {
+    pub fn new<A, B>(params: &RgepParams, context: &Context<A, B>) -> RgepEngine<R>
+        where A: Clone, B: Clone {
+        assert!(params.survival == Survival::Generational, "The ask/tell engine is generational, and does not support (mu, lambda) or (mu + lambda) survival!");
+
+        let mut rng = R::seed_from_u64(params.seed);
+        let pop = create_rgep(params, context, &mut rng);
+        let alt_pop = create_rgep_fast(params);
+        let best = pop.0[0].clone();
+
+        RgepEngine {
+            params: params.clone(),
+            bits_per_sym: context.bits_per_sym(),
+            pop,
+            alt_pop,
+            best,
+            fitness_history: Vec::with_capacity(params.num_gens),
+            progress: Progress::new(),
+            hall_of_fame: HallOfFame::new(params.hall_of_fame_size),
+            rates: rgep_mutation_rates(params, params.pop_size),
+            parent_fitnesses: Vec::new(),
+            rng,
+        }
+    }
+
+    // the individuals that need a fitness this generation
+    pub fn ask(&self) -> &PopU8 {
+        &self.pop
+    }
+
+    pub fn ask_programs<A, B>(&self, context: &Context<A, B>) -> Vec<Program<A, B>>
+        where A: Clone, B: Clone {
+        self.pop.0.iter().map(|ind| context.compile(ind)).collect()
+    }
+
+    // fitnesses must be in the same order as the individuals returned by ask
+    pub fn tell(&mut self, fitnesses: &[f64]) {
+        assert!(fitnesses.len() == self.pop.0.len(),
+                "Expected {} fitness values, but was told {}", self.pop.0.len(), fitnesses.len());
+
+        let mut fitnesses = fitnesses.to_vec();
+        self.params.objective.convert(&mut fitnesses);
+
+        if !self.parent_fitnesses.is_empty() {
+            self.rates.record_successes(count_successes(&fitnesses, &self.parent_fitnesses), fitnesses.len());
+        }
+
+        let index_fittest = fittest(&fitnesses);
+        self.fitness_history.push(fitnesses[index_fittest]);
+        self.hall_of_fame.update(&self.pop, &fitnesses, self.progress.generation);
+        if self.progress.update(fitnesses[index_fittest], fitnesses.len()) {
+            self.best = self.pop.0[index_fittest].clone();
+        }
+
+        let mut selection_fitnesses = fitnesses.clone();
+        self.params.niching.apply(&self.pop, self.bits_per_sym, &mut selection_fitnesses);
+        self.params.scaling.apply(&mut selection_fitnesses);
+        let selected = select_indices(self.params.selection, &selection_fitnesses, self.params.pop_size, self.params.elitism, &mut self.rng);
+        copy_selected(&self.pop, &mut self.alt_pop, &selected);
+        std::mem::swap(&mut self.pop, &mut self.alt_pop);
+
+        let parent_fitnesses = selected_fitnesses(&fitnesses, &selected);
+        self.rates.select(&selected);
+        self.rates.start_generation(self.progress.generation, self.params.num_gens);
+        let order = rgep_vary(&mut self.pop, &parent_fitnesses, &mut self.rates, &self.params, self.bits_per_sym, &mut self.rng);
+        self.parent_fitnesses = selected_fitnesses(&parent_fitnesses, &order);
+        rgep_reinsert(&self.hall_of_fame, &mut self.pop, &mut self.parent_fitnesses, &mut self.rates, self.params.hall_of_fame_elites);
+    }
+
+    pub fn generation(&self) -> usize {
+        self.progress.generation
+    }
+
+    pub fn best(&self) -> (&IndU8, f64) {
+        (&self.best, self.progress.best_fitness)
+    }
+
+    // the mutation rates the next generation will be varied with
+    pub fn mutation_rates(&self) -> &MutationRates {
+        &self.rates
+    }
+
+    // the termination criterion that has been met, if any
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.params.termination.check(self.params.num_gens, &self.progress)
+    }
+
+    pub fn result<A, B>(&self, context: &Context<A, B>) -> RgepResult
+        where A: Clone, B: Clone {
+        RgepResult {
+            best: self.best.clone(),
+            best_string: context.to_string(&self.best),
+            best_fitness: self.progress.best_fitness,
+            fitness_history: self.fitness_history.clone(),
+            stop_reason: self.stop_reason().unwrap_or(StopReason::External),
+            population: self.pop.clone(),
+            hall_of_fame: self.hall_of_fame.clone(),
+        }
+    }
+}
+
+#[test]
+fn test_rgep_engine_ask_tell() {
+    let context = arith_context();
+
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        num_gens: 5,
+        ..RgepParams::default()
+    };
+
+    let mut engine: RgepEngine<R> = RgepEngine::new(&params, &context);
+
+    while engine.stop_reason().is_none() {
+        let fitnesses = engine.ask_programs(&context)
+                              .iter()
+                              .map(|prog| 1.0 / (1.0 + (prog.eval(&mut (), 0.0) - 5.0).abs()))
+                              .collect::<Vec<f64>>();
+        assert_eq!(fitnesses.len(), params.pop_size);
+        engine.tell(&fitnesses);
+    }
+
+    let result = engine.result(&context);
+    assert_eq!(engine.generation(), params.num_gens);
+    assert_eq!(result.stop_reason, StopReason::MaxGenerations);
+    assert_eq!(result.fitness_history.len(), params.num_gens);
+}
@@ -0,0 +1,140 @@
+use rand::prelude::*;
+
+use crate::types::*;
+use crate::selection::*;
+use crate::evaluation::*;
+use crate::replacement::*;
+use crate::termination::*;
+use crate::observer::*;
+use crate::hall_of_fame::*;
+use crate::adaptive::*;
+
+use rgep::*;
+use rgep::context::*;
+
+
+// RGEP with (mu, lambda) or (mu + lambda) survival, with mu = params.pop_size. Each
+// generation selects lambda parents with params.selection (after niching and scaling),
+// varies and evaluates copies of them, and keeps the survivors given by params.survival.
+// rgep runs this when params.survival is not Survival::Generational.
+pub fn rgep_es_with_observer<R, A, B>(params: &RgepParams,
+                                      context: &Context<A, B>,
+                                      state: &B,
+                                      eval_ind: &EvalFunction<A, B, R>,
+                                      observer: &mut dyn Observer<u8>) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    assert!(params.hall_of_fame_elites == 0, "Hall of fame elites are only reinserted by generational runs, not (mu, lambda) or (mu + lambda) runs!");
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
+    let num_offspring = params.survival.num_offspring(params.pop_size);
+
+    let mut pop = create_rgep(params, context, rng);
+    let mut fitnesses = rgep_evaluate(&pop, context, state, eval_ind, rng);
+    params.objective.convert(&mut fitnesses);
+
+    let mut offspring = Pop(vec![Ind(Vec::with_capacity(params.ind_size)); num_offspring]);
+    let mut rates = rgep_mutation_rates(params, params.pop_size);
+
+    let bits_per_sym = context.bits_per_sym();
+
+    let mut best = pop.0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
+    hall_of_fame.update(&pop, &fitnesses, 0);
+
+    let mut progress = Progress::new();
+    progress.evaluations += fitnesses.len();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        let mut selection_fitnesses = fitnesses.clone();
+        params.niching.apply(&pop, bits_per_sym, &mut selection_fitnesses);
+        params.scaling.apply(&mut selection_fitnesses);
+        let selected = select_indices(params.selection, &selection_fitnesses, num_offspring, params.elitism, rng);
+        copy_selected(&pop, &mut offspring, &selected);
+
+        let generation = progress.generation;
+        rates.start_generation(generation, params.num_gens);
+        let mut offspring_rates = rates.clone();
+        offspring_rates.select(&selected);
+
+        let parent_fitnesses = selected_fitnesses(&fitnesses, &selected);
+        let order = rgep_vary(&mut offspring, &parent_fitnesses, &mut offspring_rates, params, bits_per_sym, rng);
+
+        let mut offspring_fitnesses = rgep_evaluate(&offspring, context, state, eval_ind, rng);
+        params.objective.convert(&mut offspring_fitnesses);
+        rates.record_successes(count_successes(&offspring_fitnesses, &selected_fitnesses(&parent_fitnesses, &order)), num_offspring);
+
+        hall_of_fame.update(&offspring, &offspring_fitnesses, generation);
+
+        let survivors = survive(params.survival, &mut pop, &mut fitnesses, &offspring, &offspring_fitnesses);
+
+        // the survivors keep their rates, with the offspring's rates after the population's
+        if let Survival::Plus { .. } = params.survival {
+            offspring_rates.rates = rates.rates.iter().chain(offspring_rates.rates.iter()).cloned().collect();
+        }
+        rates.rates = offspring_rates.rates;
+        rates.select(&survivors);
+
+        let index_fittest = fittest(&fitnesses);
+        fitness_history.push(fitnesses[index_fittest]);
+        let improved = progress.update(fitnesses[index_fittest], offspring_fitnesses.len());
+
+        let control = observer.on_generation(generation, &pop, &fitnesses);
+        if improved {
+            best = pop.0[index_fittest].clone();
+            observer.on_new_best(generation, &best, progress.best_fitness);
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    RgepResult {
+        best_string: context.to_string(&best),
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        population: pop,
+        hall_of_fame,
+    }
+}
+
+#[test]
+fn test_rgep_plus_survival_keeps_best() {
+    let context = arith_context();
+
+    let mut params = RgepParams {
+        pop_size: 10,
+        ind_size: 10,
+        num_gens: 15,
+        survival: Survival::Plus { lambda: 30 },
+        ..RgepParams::default()
+    };
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    // parents compete with their offspring, so the best fitness never gets worse
+    let result = rgep(&params, &context, &(), eval_prog);
+    assert_eq!(result.population.0.len(), params.pop_size);
+    assert!(result.fitness_history.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    params.survival = Survival::Comma { lambda: 30 };
+    let result = rgep(&params, &context, &(), eval_prog);
+    assert_eq!(result.population.0.len(), params.pop_size);
+    assert_eq!(result.fitness_history.len(), params.num_gens);
+}
@@ -1,4 +1,11 @@
 pub mod context;
+pub mod steady_state;
+pub mod engine;
+pub mod stages;
+pub mod cases;
+pub mod multi;
+pub mod alps;
+pub mod es;
 
 use std::rc::Rc;
 use std::iter;
@@ -8,9 +15,19 @@ use rand::prelude::*;
 
 use crate::types::*;
 use crate::crossover::*;
-use crate::point_mutation::*;
 use crate::rotation::*;
+use crate::transposition::*;
 use crate::selection::*;
+use crate::evaluation::*;
+use crate::termination::*;
+use crate::replacement::*;
+use crate::observer::*;
+use crate::scaling::*;
+use crate::hall_of_fame::*;
+use crate::niching::*;
+use crate::mating::*;
+use crate::length::*;
+use crate::adaptive::*;
 
 use domains::symbols::*;
 
@@ -27,12 +44,57 @@ pub struct RgepParams {
     pub prob_two_point_crossover: f64,
     pub prob_rotation: f64,
 
+    // How prob_mut changes over the run. Self-adaptive rates are inherited by offspring
+    // from their parents.
+    pub mutation_rate: MutationRate,
+
+    // whether crossover cuts between any bits, as by default, or only between whole symbols
+    pub cut_mode: CutMode,
+
+    // whether rotation is by whole symbols, as by default, keeping each symbol intact, or
+    // through any number of bits
+    pub rotation_mode: CutMode,
+
+    // GEP transposition, applied after rotation
+    pub transposition: TranspositionParams,
+
     pub pop_size: usize,
     pub ind_size: usize,
 
+    // When set, individuals start with random lengths within its bounds, and vary in length
+    // through its mutations and crossover instead of one and two point crossover.
+    // Otherwise every individual has ind_size symbols.
+    pub variable_length: Option<LengthParams>,
+
     pub elitism: usize,
+    pub selection: Selection,
+
+    // the number of distinct best individuals kept over the run, and how many of them
+    // are copied unchanged into each generation, after variation and before evaluation.
+    // Only generational runs reinsert elites, and other survival schemes reject them.
+    pub hall_of_fame_size: usize,
+    pub hall_of_fame_elites: usize,
+
+    pub objective: Objective,
+    pub scaling: FitnessScaling,
+    pub niching: Niching,
+
+    // how selected individuals are paired for crossover
+    pub mating: Mating,
 
     pub num_gens: usize,
+
+    pub termination: Termination,
+
+    // generational replacement, or (mu, lambda) or (mu + lambda) survival
+    pub survival: Survival,
+
+    // offspring created per step in steady-state mode, and how they enter the population
+    pub num_offspring: usize,
+    pub replacement: ReplacementPolicy,
+
+    // all randomness in a run is derived from this seed
+    pub seed: u64,
 }
 
 impl Default for RgepParams {
@@ -42,10 +104,27 @@ impl Default for RgepParams {
             prob_one_point_crossover: 0.6,
             prob_two_point_crossover: 0.6,
             prob_rotation: 0.01,
+            mutation_rate: MutationRate::default(),
+            cut_mode: CutMode::default(),
+            rotation_mode: CutMode::Symbol,
+            transposition: TranspositionParams::default(),
             pop_size: 25,
             ind_size: 100,
+            variable_length: None,
             elitism: 1,
+            selection: Selection::default(),
+            hall_of_fame_size: 10,
+            hall_of_fame_elites: 0,
+            objective: Objective::default(),
+            scaling: FitnessScaling::default(),
+            niching: Niching::default(),
+            mating: Mating::default(),
             num_gens: 100,
+            termination: Termination::default(),
+            survival: Survival::default(),
+            num_offspring: 2,
+            replacement: ReplacementPolicy::default(),
+            seed: 0,
         }
     }
 }
@@ -59,9 +138,18 @@ pub fn create_rgep<A, R, B>(params: &RgepParams, context: &Context<A, B>, rng: &
 
     let range = 2_u32.pow(bits_needed as u32);
 
+    if let Some(length) = params.variable_length {
+        length.check();
+    }
+
     for _ in 0..params.pop_size {
-        let mut ind_vec = Vec::with_capacity(params.ind_size);
-        for _ in 0..params.ind_size {
+        let ind_size = match params.variable_length {
+            Some(length) => rng.gen_range(length.min_size, length.max_size + 1),
+            None => params.ind_size,
+        };
+
+        let mut ind_vec = Vec::with_capacity(ind_size);
+        for _ in 0..ind_size {
             ind_vec.push(rng.gen_range(0, range) as u8);
         }
         pop.push(Ind(ind_vec));
@@ -81,50 +169,224 @@ pub fn rgep_evaluate<R, A, B>(pop: &PopU8,
                               state: &B,
                               eval_prog: &EvalFunction<A, B, R>,
                               rng: &mut R) -> Vec<f64>
-    where R: Rng,
+    where R: Rng + SeedableRng,
           A: Clone,
           B: Clone {
     let mut fitnesses = Vec::new();
 
     let mut prog = Program(Vec::with_capacity(pop.0[0].0.len()));
 
-    for ind in pop.0.iter() {
+    let gen_seed: u64 = rng.gen();
+    for (index, ind) in pop.0.iter().enumerate() {
         let mut local_state = state.clone();
+        let mut eval_rng = R::seed_from_u64(eval_seed(gen_seed, index));
         context.compile_to(&ind, &mut prog);
-        let fitness = eval_prog(&prog, &mut local_state, rng);
+        let fitness = eval_prog(&prog, &mut local_state, &mut eval_rng);
         fitnesses.push(fitness);
     }
 
     fitnesses
 }
 
+// Apply rotation, transposition, point mutation with the given rates, and one and two point
+// crossover to a population, pairing mates with params.mating. Variable-length individuals
+// have length mutation applied after point mutation, and length crossover instead of one
+// and two point crossover. fitnesses are those of the individuals in the population, such
+// as the fitnesses they were selected with. Returns the order the population was arranged
+// in for mating, so that pop.0[index] was varied from individual order[index].
+pub fn rgep_vary<R: Rng>(pop: &mut PopU8,
+                         fitnesses: &[f64],
+                         rates: &mut MutationRates,
+                         params: &RgepParams,
+                         bits_per_sym: usize,
+                         rng: &mut R) -> Vec<usize> {
+    rotation(pop, bits_per_sym, params.rotation_mode, params.prob_rotation, rng);
+    transposition(pop, &params.transposition, rng);
+    rates.mutate(pop, bits_per_sym, rng);
+    if let Some(length) = params.variable_length {
+        length_mutation(pop, bits_per_sym, &length, rng);
+    }
+
+    let mut fitnesses = fitnesses.to_vec();
+    let order = mate(params.mating, pop, &mut fitnesses, bits_per_sym, &mut |pop: &mut PopU8, rng: &mut R| {
+        match params.variable_length {
+            Some(length) => {
+                length_crossover(pop, bits_per_sym, params.cut_mode, &length, rng);
+            },
+
+            None => {
+                crossover_one_point(pop, params.ind_size, bits_per_sym, params.cut_mode, params.prob_one_point_crossover, rng);
+                crossover_two_point(pop, params.ind_size, bits_per_sym, params.cut_mode, params.prob_two_point_crossover, rng);
+            },
+        }
+    }, rng);
+
+    rates.select(&order);
+
+    order
+}
+
+// the mutation rates for a population of num_inds individuals, as set by params
+pub fn rgep_mutation_rates(params: &RgepParams, num_inds: usize) -> MutationRates {
+    MutationRates::new(params.mutation_rate, params.prob_mut, num_inds)
+}
+
+// Reinsert the hall of fame's elites into a varied population, so that they are evaluated
+// unchanged. parent_fitnesses are those of each individual's parent, and the elites' become
+// their archived fitnesses, so that they are not counted as successful offspring, and their
+// mutation rates start from the current rate.
+pub fn rgep_reinsert(hall_of_fame: &HallOfFame<u8>,
+                     pop: &mut PopU8,
+                     parent_fitnesses: &mut [f64],
+                     rates: &mut MutationRates,
+                     num_elites: usize) {
+    let indices = hall_of_fame.reinsert(pop, num_elites);
+    for (member, index) in hall_of_fame.members.iter().zip(indices.iter()) {
+        parent_fitnesses[*index] = member.fitness;
+    }
+
+    rates.reset(&indices);
+}
+
+#[derive(Clone, Debug)]
+pub struct RgepResult {
+    pub best: Ind<u8>,
+    pub best_string: String,
+    pub best_fitness: f64,
+
+    // best fitness found in each generation
+    pub fitness_history: Vec<f64>,
+
+    pub stop_reason: StopReason,
+
+    // the population at the end of the run
+    pub population: PopU8,
+
+    // the best distinct individuals found over the whole run
+    pub hall_of_fame: HallOfFame<u8>,
+}
+
 pub fn rgep<R, A, B>(params: &RgepParams,
                      context: &Context<A, B>,
                      state: &B,
-                     eval_ind: &EvalFunction<A, B, R>,
-                     rng: &mut R) -> PopU8
-    where R: Rng, A: Clone, B: Clone {
+                     eval_ind: &EvalFunction<A, B, R>) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    rgep_with_observer(params, context, state, eval_ind, &mut NullObserver)
+}
+
+pub fn rgep_with_observer<R, A, B>(params: &RgepParams,
+                                   context: &Context<A, B>,
+                                   state: &B,
+                                   eval_ind: &EvalFunction<A, B, R>,
+                                   observer: &mut dyn Observer<u8>) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    if params.survival != Survival::Generational {
+        return es::rgep_es_with_observer(params, context, state, eval_ind, observer);
+    }
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
     let mut pop = create_rgep(&params, &context, rng);
     let mut alt_pop = create_rgep_fast(&params);
 
     let bits_per_sym = context.bits_per_sym();
 
-    for _ in 0..params.num_gens {
-        //rotation(&mut pop, params.prob_rotation, rng);
-        //point_mutation(&mut pop, bits_per_sym, params.prob_mut, rng);
-        //crossover_one_point(&mut pop, params.ind_size, bits_per_sym, params.prob_one_point_crossover, rng);
-        //crossover_two_point(&mut pop, params.ind_size, bits_per_sym, params.prob_two_point_crossover, rng);
-        //let fitnesses = rgep_evaluate(&pop, context, state, eval_ind, rng);
-        //stochastic_universal_sampling(&pop, &mut alt_pop, fitnesses, params.elitism, rng);
+    let mut best = pop.0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
 
-        //std::mem::swap(&mut pop, &mut alt_pop);
+    // the fitnesses of the individuals each individual was selected from, for mating and
+    // for counting successful offspring
+    let mut parent_fitnesses = vec![0.0; params.pop_size];
+    let mut rates = rgep_mutation_rates(params, params.pop_size);
+
+    let mut progress = Progress::new();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        let generation = progress.generation;
+        rates.start_generation(generation, params.num_gens);
+        let order = rgep_vary(&mut pop, &parent_fitnesses, &mut rates, params, bits_per_sym, rng);
+        let mut offspring_parent_fitnesses = selected_fitnesses(&parent_fitnesses, &order);
+        rgep_reinsert(&hall_of_fame, &mut pop, &mut offspring_parent_fitnesses, &mut rates, params.hall_of_fame_elites);
+
+        let mut fitnesses = rgep_evaluate(&pop, context, state, eval_ind, rng);
+        params.objective.convert(&mut fitnesses);
+
+        if generation > 0 {
+            rates.record_successes(count_successes(&fitnesses, &offspring_parent_fitnesses), fitnesses.len());
+        }
+
+        let index_fittest = fittest(&fitnesses);
+        fitness_history.push(fitnesses[index_fittest]);
+        let improved = progress.update(fitnesses[index_fittest], fitnesses.len());
+        hall_of_fame.update(&pop, &fitnesses, generation);
+
+        let control = observer.on_generation(generation, &pop, &fitnesses);
+        if improved {
+            best = pop.0[index_fittest].clone();
+            observer.on_new_best(generation, &best, progress.best_fitness);
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+
+        let mut selection_fitnesses = fitnesses.clone();
+        params.niching.apply(&pop, bits_per_sym, &mut selection_fitnesses);
+        params.scaling.apply(&mut selection_fitnesses);
+        let selected = select_indices(params.selection, &selection_fitnesses, params.pop_size, params.elitism, rng);
+        copy_selected(&pop, &mut alt_pop, &selected);
+        parent_fitnesses = selected_fitnesses(&fitnesses, &selected);
+        rates.select(&selected);
+
+        std::mem::swap(&mut pop, &mut alt_pop);
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    RgepResult {
+        best_string: context.to_string(&best),
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        population: pop,
+        hall_of_fame,
+    }
+}
+
+// the context shared by the RGEP run tests, with the terminals 0, 1 and 2 and addition
+#[cfg(test)]
+pub fn arith_context() -> Context<f64, ()> {
+    use domains::arith::{plus_sym, one_sym, zero_sym, two_sym};
+
+    Context {
+        terminals: vec!(zero_sym(), one_sym(), two_sym()),
+        functions: vec!(plus_sym()),
+        default: 0.0,
     }
+}
 
-    pop
+// the fitness shared by the RGEP run tests, which is 1 for programs that evaluate to 5
+// and falls towards 0 further away
+#[cfg(test)]
+pub fn distance_to_five(prog: &Program<f64, ()>, state: &mut (), _rng: &mut R) -> f64 {
+    1.0 / (1.0 + (prog.eval(state, 0.0) - 5.0).abs())
 }
 
 #[test]
 fn test_point_mutation_flips_bits() {
+    use crate::point_mutation::*;
     use domains::arith::{plus_sym, one_sym, zero_sym, two_sym};
 
     let terminals: Vec<Sym<f64, ()>> =
@@ -145,6 +407,7 @@ fn test_point_mutation_flips_bits() {
         ind_size: num_words,
         num_gens: 100,
         elitism: 0,
+        ..RgepParams::default()
     };
 
     let context = Context {
@@ -177,3 +440,365 @@ fn test_point_mutation_flips_bits() {
     assert!((percent_ones - 0.5).abs() < 0.005, format!("Percent ones was expected to be 0.5, but was {}", percent_ones));
 }
 
+
+#[test]
+fn test_rgep_result_tracks_best() {
+    let context = arith_context();
+
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        num_gens: 10,
+        ..RgepParams::default()
+    };
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    let result = rgep(&params, &context, &(), eval_prog);
+
+    assert_eq!(result.fitness_history.len(), params.num_gens);
+    assert_eq!(result.best_string, context.to_string(&result.best));
+    assert!(result.fitness_history.iter().all(|fitness| *fitness <= result.best_fitness));
+
+    let famer = result.hall_of_fame.best().unwrap();
+    assert_eq!(famer.fitness, result.best_fitness);
+    assert_eq!(result.hall_of_fame.members.len(), params.hall_of_fame_size);
+}
+
+#[test]
+fn test_rgep_hall_of_fame_elites() {
+    use crate::test_support::*;
+
+    let context = arith_context();
+
+    // with heavy mutation, the archive's best only survives if it is reinserted unchanged
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        num_gens: 20,
+        prob_mut: 0.2,
+        hall_of_fame_elites: 1,
+        ..RgepParams::default()
+    };
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    let mut observer = ReinsertionObserver { hall_of_fame: HallOfFame::new(1) };
+    let result = rgep_with_observer(&params, &context, &(), eval_prog, &mut observer);
+    assert_eq!(observer.hall_of_fame.best(), result.hall_of_fame.best());
+}
+
+#[test]
+fn test_rgep_observer_can_stop() {
+    struct CountObserver {
+        generations: usize,
+        new_bests: usize,
+        ended: bool,
+    }
+
+    impl Observer<u8> for CountObserver {
+        fn on_generation(&mut self, generation: usize, _pop: &PopU8, _fitnesses: &Vec<f64>) -> Control {
+            assert_eq!(generation, self.generations);
+            self.generations += 1;
+            if self.generations == 3 {
+                Control::Stop
+            } else {
+                Control::Continue
+            }
+        }
+
+        fn on_new_best(&mut self, _generation: usize, _ind: &IndU8, _fitness: f64) {
+            self.new_bests += 1;
+        }
+
+        fn on_end(&mut self, stop_reason: StopReason, _best: &IndU8, _best_fitness: f64) {
+            assert_eq!(stop_reason, StopReason::Observer);
+            self.ended = true;
+        }
+    }
+
+    let context = arith_context();
+
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        ..RgepParams::default()
+    };
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    let mut observer = CountObserver { generations: 0, new_bests: 0, ended: false };
+    let result = rgep_with_observer(&params, &context, &(), eval_prog, &mut observer);
+
+    assert_eq!(result.stop_reason, StopReason::Observer);
+    assert_eq!(observer.generations, 3);
+    assert!(observer.new_bests >= 1);
+    assert!(observer.ended);
+}
+
+#[test]
+fn test_rgep_seed_reproduces_run() {
+    let context = arith_context();
+
+    let mut params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        num_gens: 10,
+        seed: 1234,
+        ..RgepParams::default()
+    };
+
+    // a noisy fitness function, so the evaluation streams must be reproducible too
+    let eval_prog: &EvalFunction<f64, (), R> =
+        &|prog: &Program<f64, ()>, state: &mut (), rng: &mut R| -> f64 {
+            rng.gen::<f64>() / (1.0 + (prog.eval(state, 0.0) - 5.0).abs())
+        };
+
+    let first = rgep(&params, &context, &(), eval_prog);
+    let second = rgep(&params, &context, &(), eval_prog);
+
+    assert_eq!(first.population, second.population);
+    assert_eq!(first.fitness_history, second.fitness_history);
+    assert_eq!(first.best, second.best);
+
+    params.seed = 4321;
+    let third = rgep(&params, &context, &(), eval_prog);
+    assert!(first.population != third.population);
+}
+
+#[test]
+fn test_rgep_mating_schemes() {
+    let context = arith_context();
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    for mating in vec!(Mating::Shuffled, Mating::Tournament { size: 3 }, Mating::IncestPrevention { threshold: 4 }) {
+        let params = RgepParams {
+            pop_size: 20,
+            ind_size: 10,
+            num_gens: 10,
+            mating,
+            ..RgepParams::default()
+        };
+
+        let result = rgep(&params, &context, &(), eval_prog);
+
+        assert_eq!(result.population.0.len(), params.pop_size);
+        assert_eq!(result.fitness_history.len(), params.num_gens);
+        assert!(result.fitness_history.iter().all(|fitness| *fitness <= result.best_fitness));
+    }
+}
+
+#[test]
+fn test_rgep_fitness_sharing() {
+    let context = arith_context();
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    for distance in vec!(Distance::Hamming, Distance::Genes) {
+        let params = RgepParams {
+            pop_size: 20,
+            ind_size: 10,
+            num_gens: 10,
+            niching: Niching::Sharing { radius: 4.0, alpha: 1.0, distance },
+            ..RgepParams::default()
+        };
+
+        let result = rgep(&params, &context, &(), eval_prog);
+
+        assert_eq!(result.population.0.len(), params.pop_size);
+        assert_eq!(result.fitness_history.len(), params.num_gens);
+        assert!(result.fitness_history.iter().all(|fitness| *fitness <= result.best_fitness));
+    }
+}
+
+#[test]
+fn test_rgep_rotation_keeps_symbols() {
+    let context = arith_context();
+    let mut rng = R::seed_from_u64(0);
+
+    // by default rotation moves whole symbols, so each individual keeps its symbols
+    let params = RgepParams {
+        pop_size: 20,
+        ..RgepParams::default()
+    };
+
+    let mut pop = create_rgep(&params, &context, &mut rng);
+    let before = pop.clone();
+    rotation(&mut pop, context.bits_per_sym(), params.rotation_mode, 1.0, &mut rng);
+
+    assert_ne!(pop, before);
+    for (ind, original) in pop.0.iter().zip(before.0.iter()) {
+        let mut syms = ind.0.clone();
+        let mut original_syms = original.0.clone();
+        syms.sort();
+        original_syms.sort();
+        assert_eq!(syms, original_syms);
+    }
+}
+
+#[test]
+fn test_rgep_variable_length() {
+    let context = arith_context();
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    for crossover in vec!(LengthCrossover::Homologous, LengthCrossover::TwoCut) {
+        let length = LengthParams {
+            min_size: 3,
+            max_size: 15,
+            prob_insertion: 0.2,
+            prob_deletion: 0.2,
+            prob_duplication: 0.1,
+            crossover,
+            ..LengthParams::default()
+        };
+
+        // cut only between symbols, so crossing individuals of different lengths
+        // exchanges whole symbols
+        let params = RgepParams {
+            pop_size: 20,
+            num_gens: 20,
+            variable_length: Some(length),
+            cut_mode: CutMode::Symbol,
+            ..RgepParams::default()
+        };
+
+        let result = rgep(&params, &context, &(), eval_prog);
+
+        assert!(result.population.0.iter().all(|ind| ind.0.len() >= 3 && ind.0.len() <= 15));
+        assert!(result.population.0.iter().any(|ind| ind.0.len() != result.population.0[0].0.len()));
+        assert_eq!(result.fitness_history.len(), params.num_gens);
+    }
+}
+
+#[test]
+fn test_rgep_adaptive_mutation() {
+    use crate::rgep::engine::*;
+
+    let context = arith_context();
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    let schedules = vec!(MutationRate::Linear { final_rate: 0.0001 },
+                         MutationRate::Exponential { decay: 0.9 },
+                         MutationRate::OneFifth { factor: 1.2 },
+                         MutationRate::SelfAdaptive { learning_rate: 0.5 });
+
+    for mutation_rate in schedules {
+        for survival in vec!(Survival::Generational, Survival::Plus { lambda: 10 }) {
+            let params = RgepParams {
+                pop_size: 20,
+                ind_size: 10,
+                num_gens: 10,
+                prob_mut: 0.05,
+                mutation_rate,
+                survival,
+                ..RgepParams::default()
+            };
+
+            let result = rgep(&params, &context, &(), eval_prog);
+
+            assert_eq!(result.population.0.len(), params.pop_size);
+            assert_eq!(result.fitness_history.len(), params.num_gens);
+        }
+    }
+
+    // the scheduled rate decays each generation
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        num_gens: 10,
+        prob_mut: 0.1,
+        mutation_rate: MutationRate::Exponential { decay: 0.5 },
+        ..RgepParams::default()
+    };
+    let mut engine: RgepEngine<R> = RgepEngine::new(&params, &context);
+    for generation in 1..4 {
+        engine.tell(&vec![1.0; params.pop_size]);
+        assert_eq!(engine.mutation_rates().rate, 0.1 * 0.5f64.powi(generation));
+    }
+
+    // the 1/5th rule raises the rate when every child beats its parent, and lowers it when none do
+    let params = RgepParams {
+        mutation_rate: MutationRate::OneFifth { factor: 2.0 },
+        ..params
+    };
+    let mut engine: RgepEngine<R> = RgepEngine::new(&params, &context);
+    engine.tell(&vec![1.0; params.pop_size]);
+    assert_eq!(engine.mutation_rates().rate, 0.1);
+    engine.tell(&vec![2.0; params.pop_size]);
+    assert_eq!(engine.mutation_rates().rate, 0.2);
+    engine.tell(&vec![0.5; params.pop_size]);
+    engine.tell(&vec![0.25; params.pop_size]);
+    assert_eq!(engine.mutation_rates().rate, 0.05);
+
+    // self-adaptive rates stay with their individuals when they are shuffled for mating
+    let params = RgepParams {
+        mutation_rate: MutationRate::SelfAdaptive { learning_rate: 0.0 },
+        mating: Mating::Shuffled,
+        ..params
+    };
+    let mut rng = R::seed_from_u64(0);
+    let mut pop = create_rgep(&params, &context, &mut rng);
+    let mut rates = rgep_mutation_rates(&params, params.pop_size);
+    rates.rates = (0..params.pop_size).map(|index| 0.01 * (index + 1) as f64).collect();
+    let before = rates.rates.clone();
+
+    let order = rgep_vary(&mut pop, &vec![1.0; params.pop_size], &mut rates, &params, context.bits_per_sym(), &mut rng);
+    assert!(order.iter().enumerate().any(|(index, parent)| index != *parent));
+    assert_eq!(rates.rates, order.iter().map(|parent| before[*parent]).collect::<Vec<f64>>());
+}
+
+#[test]
+fn test_rgep_transposition() {
+    let context = arith_context();
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 12,
+        num_gens: 10,
+        transposition: TranspositionParams { prob_is: 1.0, prob_ris: 1.0, prob_gene: 1.0, max_length: 3, gene_size: 4 },
+        ..RgepParams::default()
+    };
+
+    let result = rgep(&params, &context, &(), eval_prog);
+
+    assert!(result.population.0.iter().all(|ind| ind.0.len() == params.ind_size));
+    assert_eq!(result.fitness_history.len(), params.num_gens);
+
+    // the same seed without transposition must end with different genomes
+    let without = RgepParams {
+        transposition: TranspositionParams { prob_is: 0.0, prob_ris: 0.0, prob_gene: 0.0, ..params.transposition },
+        ..params
+    };
+    let without_result = rgep(&without, &context, &(), eval_prog);
+
+    assert_ne!(result.population.0, without_result.population.0);
+
+    // with RIS alone, and gene transposition leaving the 2 symbols after the last whole
+    // gene in place, each individual with a function ends with one copied to the root.
+    // point_mutate needs a rate above 0, so this one flips nothing over the run.
+    let ris_only = RgepParams {
+        prob_mut: 1e-9,
+        prob_one_point_crossover: 0.0,
+        prob_two_point_crossover: 0.0,
+        prob_rotation: 0.0,
+        elitism: 0,
+        transposition: TranspositionParams { prob_is: 0.0, prob_ris: 1.0, prob_gene: 1.0, max_length: 3, gene_size: 5 },
+        ..without
+    };
+    // a flat fitness, so that selection does not favour programs ending with a function
+    let eval_flat: &EvalFunction<f64, (), R> = &|_prog: &Program<f64, ()>, _state: &mut (), _rng: &mut R| 1.0;
+    let ris_result = rgep(&ris_only, &context, &(), eval_flat);
+
+    assert!(ris_result.population.0.iter().all(|ind| ind.0.len() == ris_only.ind_size));
+    assert!(ris_result.population.0.iter().any(|ind| ind.0.iter().any(|code| is_function_code(*code))));
+    for ind in ris_result.population.0.iter() {
+        if ind.0.iter().any(|code| is_function_code(*code)) {
+            assert!(is_function_code(ind.0[ris_only.ind_size - 1]));
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use rand::prelude::*;
+
+use crate::types::*;
+use crate::selection::*;
+use crate::evaluation::*;
+use crate::termination::*;
+use crate::observer::*;
+use crate::nsga::*;
+use crate::hall_of_fame::*;
+use crate::replacement::*;
+
+use rgep::*;
+use rgep::cases::*;
+use rgep::context::*;
+
+
+// An evaluation function returning a vector of objectives for a program, all of which
+// are minimized, such as its error and prog.0.len() for its size.
+pub type ObjectiveEvalFunction<A, B, R> = CaseEvalFunction<A, B, R>;
+
+// a member of the final Pareto front of a multi-objective run
+#[derive(Clone, Debug)]
+pub struct ParetoMember {
+    pub ind: IndU8,
+    pub string: String,
+    pub objectives: Vec<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RgepNsgaResult {
+    // best is judged by the first objective, with fitness 1 / (1 + objective)
+    pub result: RgepResult,
+
+    // the distinct individuals in the final population's Pareto front
+    pub front: Vec<ParetoMember>,
+}
+
+// the fitness reported for each individual, from its first objective
+pub fn first_objective_fitnesses(objectives: &[Vec<f64>]) -> Vec<f64> {
+    objectives.iter().map(|ind_objectives| 1.0 / (1.0 + ind_objectives[0].max(0.0))).collect()
+}
+
+// NSGA-II RGEP. Each generation chooses parents by crowded tournament, varies them with
+// the usual operators, and keeps the best params.pop_size of the parents and offspring
+// by non-dominated sorting and crowding distance. params.selection, params.elitism and
+// params.objective are not used.
+pub fn rgep_nsga2<R, A, B>(params: &RgepParams,
+                           context: &Context<A, B>,
+                           state: &B,
+                           eval_objectives: &ObjectiveEvalFunction<A, B, R>) -> RgepNsgaResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    rgep_nsga2_with_observer(params, context, state, eval_objectives, &mut NullObserver)
+}
+
+pub fn rgep_nsga2_with_observer<R, A, B>(params: &RgepParams,
+                                         context: &Context<A, B>,
+                                         state: &B,
+                                         eval_objectives: &ObjectiveEvalFunction<A, B, R>,
+                                         observer: &mut dyn Observer<u8>) -> RgepNsgaResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    assert!(params.hall_of_fame_elites == 0, "Hall of fame elites are only reinserted by generational runs, not NSGA-II runs!");
+    assert!(params.survival == Survival::Generational, "NSGA-II runs keep their own survivors, and do not support (mu, lambda) or (mu + lambda) survival!");
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
+    let mut pop = create_rgep(params, context, rng);
+    let mut offspring = create_rgep_fast(params);
+    let mut objectives = rgep_evaluate_cases(&pop, context, state, eval_objectives, rng);
+
+    let bits_per_sym = context.bits_per_sym();
+
+    let mut best = pop.0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
+    let mut rates = rgep_mutation_rates(params, params.pop_size);
+
+    let mut progress = Progress::new();
+    progress.evaluations += pop.0.len();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        let (ranks, distances) = rank_and_crowding(&objectives);
+        let selected = crowded_tournament_indices(&ranks, &distances, params.pop_size, rng);
+        copy_selected(&pop, &mut offspring, &selected);
+        let parent_fitnesses = selected_fitnesses(&first_objective_fitnesses(&objectives), &selected);
+
+        rates.start_generation(progress.generation, params.num_gens);
+        let mut offspring_rates = rates.clone();
+        offspring_rates.select(&selected);
+        let order = rgep_vary(&mut offspring, &parent_fitnesses, &mut offspring_rates, params, bits_per_sym, rng);
+        let offspring_objectives = rgep_evaluate_cases(&offspring, context, state, eval_objectives, rng);
+
+        // an offspring is a success if it dominates its parent
+        let successes = offspring_objectives.iter()
+                                            .zip(order.iter())
+                                            .filter(|(child, index)| dominates(child, &objectives[selected[**index]]))
+                                            .count();
+        rates.record_successes(successes, offspring_objectives.len());
+
+        let mut combined = pop.clone();
+        combined.0.extend(offspring.0.iter().cloned());
+        let mut combined_objectives = objectives;
+        combined_objectives.extend(offspring_objectives);
+
+        let survivors = nsga2_survivors(&combined_objectives, params.pop_size);
+        copy_selected(&combined, &mut pop, &survivors);
+        rates.rates = rates.rates.iter().chain(offspring_rates.rates.iter()).cloned().collect();
+        rates.select(&survivors);
+        objectives = survivors.iter().map(|index| combined_objectives[*index].clone()).collect();
+
+        let fitnesses = first_objective_fitnesses(&objectives);
+
+        let generation = progress.generation;
+        let index_fittest = fittest(&fitnesses);
+        fitness_history.push(fitnesses[index_fittest]);
+        let improved = progress.update(fitnesses[index_fittest], offspring.0.len());
+        hall_of_fame.update(&pop, &fitnesses, generation);
+
+        let control = observer.on_generation(generation, &pop, &fitnesses);
+        if improved {
+            best = pop.0[index_fittest].clone();
+            observer.on_new_best(generation, &best, progress.best_fitness);
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    let mut front: Vec<ParetoMember> = Vec::new();
+    for index in pareto_front(&objectives) {
+        if front.iter().all(|member| member.ind != pop.0[index]) {
+            front.push(ParetoMember {
+                ind: pop.0[index].clone(),
+                string: context.to_string(&pop.0[index]),
+                objectives: objectives[index].clone(),
+            });
+        }
+    }
+
+    RgepNsgaResult {
+        result: RgepResult {
+            best_string: context.to_string(&best),
+            best,
+            best_fitness: progress.best_fitness,
+            fitness_history,
+            stop_reason,
+            population: pop,
+            hall_of_fame,
+        },
+        front,
+    }
+}
+
+#[test]
+fn test_rgep_nsga2_front() {
+    use domains::symbols::*;
+
+    let context = arith_context();
+
+    let params = RgepParams {
+        pop_size: 30,
+        ind_size: 10,
+        num_gens: 20,
+        seed: 3,
+        ..RgepParams::default()
+    };
+
+    // error against 5 traded off against program size
+    let eval_objectives: &ObjectiveEvalFunction<f64, (), R> =
+        &|prog: &Program<f64, ()>, state: &mut (), _rng: &mut R| -> Vec<f64> {
+            vec!((prog.eval(state, 0.0) - 5.0).abs(), prog.0.len() as f64)
+        };
+
+    let nsga_result = rgep_nsga2(&params, &context, &(), eval_objectives);
+
+    assert_eq!(nsga_result.result.population.0.len(), params.pop_size);
+    assert_eq!(nsga_result.result.fitness_history.len(), params.num_gens);
+    assert!(!nsga_result.front.is_empty());
+
+    for member in nsga_result.front.iter() {
+        assert_eq!(member.string, context.to_string(&member.ind));
+        assert!(nsga_result.front.iter().all(|other| !dominates(&other.objectives, &member.objectives)));
+    }
+}
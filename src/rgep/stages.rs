@@ -0,0 +1,588 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use rand::prelude::*;
+
+use crate::types::*;
+use crate::crossover::*;
+use crate::point_mutation::*;
+use crate::rotation::*;
+use crate::transposition::*;
+use crate::selection::*;
+use crate::evaluation::*;
+use crate::termination::*;
+use crate::observer::*;
+use crate::stage::*;
+use crate::scaling::*;
+use crate::hall_of_fame::*;
+use crate::niching::*;
+use crate::mating::*;
+use crate::length::*;
+use crate::adaptive::*;
+
+use rgep::*;
+use rgep::context::*;
+
+
+// The state of a stage-based RGEP run. This mirrors GaState, but owns the
+// Context and EvalFunction so that stages can be built from 'static getters.
+pub struct RgepState<A: Clone + 'static, B: Clone + 'static, R> {
+    pub params: RgepParams,
+    pub bits_per_sym: usize,
+
+    pub context: Rc<Context<A, B>>,
+    pub state: B,
+    pub eval: Rc<EvalFunction<A, B, R>>,
+
+    pub population: Rc<RefCell<PopU8>>,
+    pub alt_population: Rc<RefCell<PopU8>>,
+    pub fitnesses: Rc<RefCell<Vec<f64>>>,
+
+    // the fitness of each individual's parent, kept with the individuals through mating and
+    // reinsertion, for mating and for counting successful offspring
+    pub parent_fitnesses: Rc<RefCell<Vec<f64>>>,
+
+    // the indices chosen by the last selection, recorded by the rgep selection stages
+    pub selected: Rc<RefCell<Vec<usize>>>,
+
+    // the best individuals found over the run, updated by rgep_staged
+    pub hall_of_fame: Rc<RefCell<HallOfFame<u8>>>,
+
+    // the point mutation rate, following params.mutation_rate's schedule
+    pub rates: Rc<RefCell<MutationRates>>,
+}
+
+impl<A, B, R> RgepState<A, B, R>
+    where A: Clone + 'static,
+          B: Clone + 'static,
+          R: Rng {
+    pub fn create_rgep_state(params: &RgepParams,
+                             context: Rc<Context<A, B>>,
+                             state: B,
+                             eval: Rc<EvalFunction<A, B, R>>,
+                             rng: &mut R) -> RgepState<A, B, R> {
+        let population = create_rgep(params, &context, rng);
+        let alt_population = create_rgep_fast(params);
+        let fitnesses = vec![0.0; params.pop_size];
+
+        RgepState {
+            params: params.clone(),
+            bits_per_sym: context.bits_per_sym(),
+            context,
+            state,
+            eval,
+            population: Rc::new(RefCell::new(population)),
+            alt_population: Rc::new(RefCell::new(alt_population)),
+            fitnesses: Rc::new(RefCell::new(fitnesses)),
+            parent_fitnesses: Rc::new(RefCell::new(vec![0.0; params.pop_size])),
+            selected: Rc::new(RefCell::new(Vec::new())),
+            hall_of_fame: Rc::new(RefCell::new(HallOfFame::new(params.hall_of_fame_size))),
+            rates: Rc::new(RefCell::new(rgep_mutation_rates(params, params.pop_size))),
+        }
+    }
+}
+
+pub struct RgepEvalState<A: Clone + 'static, B: Clone + 'static, R> {
+    pub population: Rc<RefCell<PopU8>>,
+    pub context: Rc<Context<A, B>>,
+    pub state: B,
+    pub eval: Rc<EvalFunction<A, B, R>>,
+    pub fitnesses: Rc<RefCell<Vec<f64>>>,
+}
+
+impl<A, B, R> RgepEvalState<A, B, R>
+    where A: Clone + 'static,
+          B: Clone + 'static {
+    pub fn new(population: Rc<RefCell<PopU8>>,
+               context: Rc<Context<A, B>>,
+               state: B,
+               eval: Rc<EvalFunction<A, B, R>>,
+               fitnesses: Rc<RefCell<Vec<f64>>>) -> RgepEvalState<A, B, R> {
+        RgepEvalState {
+            population,
+            context,
+            state,
+            eval,
+            fitnesses,
+        }
+    }
+}
+
+// evaluate each individual's program through the Context
+pub fn rgep_evaluate_stage<S, A, B, R>(getter: Getter<S, RgepEvalState<A, B, R>>) -> Stage<S, R>
+    where R: Rng + SeedableRng + 'static,
+          A: Clone + 'static,
+          B: Clone + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let eval_state = getter(state);
+        let fitnesses = rgep_evaluate(&eval_state.population.borrow(),
+                                      &eval_state.context,
+                                      &eval_state.state,
+                                      &*eval_state.eval,
+                                      rng);
+        *eval_state.fitnesses.borrow_mut() = fitnesses;
+    });
+
+    f
+}
+
+pub fn rgep_rotation_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    rotation_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        RotationState::new(state.population.clone(), state.params.prob_rotation, state.bits_per_sym, state.params.rotation_mode)
+    }))
+}
+
+pub fn rgep_is_transposition_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    is_transposition_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        TranspositionState::new(state.population.clone(), state.params.transposition.prob_is, state.params.transposition.max_length)
+    }))
+}
+
+pub fn rgep_ris_transposition_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    ris_transposition_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        TranspositionState::new(state.population.clone(), state.params.transposition.prob_ris, state.params.transposition.max_length)
+    }))
+}
+
+pub fn rgep_gene_transposition_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    gene_transposition_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        GeneTranspositionState::new(state.population.clone(), state.params.transposition.prob_gene, state.params.transposition.gene_size)
+    }))
+}
+
+pub fn rgep_point_mutation_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    point_mutation_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        PmState::new(state.population.clone(), state.params.prob_mut, state.bits_per_sym)
+    }))
+}
+
+// point mutation with the run's current scheduled rate
+pub fn rgep_adaptive_mutation_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    adaptive_mutation_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        AdaptiveMutationState::new(state.population.clone(), state.rates.clone(), state.bits_per_sym)
+    }))
+}
+
+pub fn rgep_crossover_one_point_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    crossover_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        CrossoverState::new(state.population.clone(), state.params.prob_one_point_crossover, state.bits_per_sym, state.params.cut_mode)
+    }))
+}
+
+pub fn rgep_crossover_two_point_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    crossover_two_point_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        CrossoverState::new(state.population.clone(), state.params.prob_two_point_crossover, state.bits_per_sym, state.params.cut_mode)
+    }))
+}
+
+pub fn rgep_crossover_n_point_stage<A, B, R>(num_points: usize, pc: f64) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    crossover_n_point_stage(Rc::new(move |state: &RgepState<A, B, R>| {
+        NPointCrossoverState::new(state.population.clone(), pc, state.bits_per_sym, state.params.cut_mode, num_points)
+    }))
+}
+
+pub fn rgep_crossover_uniform_bits_stage<A, B, R>(prob_swap: f64, pc: f64) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    crossover_uniform_bits_stage(Rc::new(move |state: &RgepState<A, B, R>| {
+        UniformCrossoverState::new(state.population.clone(), pc, state.bits_per_sym, prob_swap)
+    }))
+}
+
+pub fn rgep_crossover_uniform_symbols_stage<A, B, R>(prob_swap: f64, pc: f64) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    crossover_uniform_symbols_stage(Rc::new(move |state: &RgepState<A, B, R>| {
+        UniformCrossoverState::new(state.population.clone(), pc, state.bits_per_sym, prob_swap)
+    }))
+}
+
+// insertion, deletion and duplication mutation for variable-length individuals
+pub fn rgep_length_mutation_stage<A, B, R>(length: LengthParams) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    length_mutation_stage(Rc::new(move |state: &RgepState<A, B, R>| {
+        LengthMutationState::new(state.population.clone(), state.bits_per_sym, length)
+    }))
+}
+
+// crossover that keeps variable-length individuals within the bounds in length
+pub fn rgep_length_crossover_stage<A, B, R>(length: LengthParams) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    length_crossover_stage(Rc::new(move |state: &RgepState<A, B, R>| {
+        LengthCrossoverState::new(state.population.clone(), state.bits_per_sym, state.params.cut_mode, length)
+    }))
+}
+
+// Run crossover on mates paired with params.mating, using the parent fitnesses. As with
+// rgep_vary, the parent fitnesses and mutation rates are rearranged along with the population.
+pub fn rgep_mating_stage<A, B, R>(crossover: Stage<RgepState<A, B, R>, R>) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    let f: Rc<dyn Fn(&RgepState<A, B, R>, &mut R)> = Rc::new(move |state, rng| {
+        let mut pop = state.population.borrow().clone();
+        let mut parent_fitnesses = state.parent_fitnesses.borrow().clone();
+        let order = mate(state.params.mating, &mut pop, &mut parent_fitnesses, state.bits_per_sym, &mut |arranged_pop: &mut PopU8, rng: &mut R| {
+            std::mem::swap(&mut *state.population.borrow_mut(), arranged_pop);
+            crossover(state, rng);
+            std::mem::swap(&mut *state.population.borrow_mut(), arranged_pop);
+        }, rng);
+
+        *state.population.borrow_mut() = pop;
+        *state.parent_fitnesses.borrow_mut() = parent_fitnesses;
+        state.rates.borrow_mut().select(&order);
+    });
+
+    f
+}
+
+pub fn rgep_eval_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + SeedableRng + 'static {
+    rgep_evaluate_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        RgepEvalState::new(state.population.clone(),
+                           state.context.clone(),
+                           state.state.clone(),
+                           state.eval.clone(),
+                           state.fitnesses.clone())
+    }))
+}
+
+// Select with the scheme given by selection, recording the selected indices so that
+// rgep_staged can keep each individual's parent fitness and mutation rate with it. The
+// fitnesses are left aligned with the selected individuals.
+fn rgep_select_stage<A, B, R, F>(selection: F) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static,
+          B: Clone + 'static,
+          R: Rng + 'static,
+          F: Fn(&RgepState<A, B, R>) -> Selection + 'static {
+    let f: Rc<dyn Fn(&RgepState<A, B, R>, &mut R)> = Rc::new(move |state, rng| {
+        let mut fitnesses = state.fitnesses.borrow_mut();
+        let selected = select_indices(selection(state), &fitnesses, state.params.pop_size, state.params.elitism, rng);
+        copy_selected(&state.population.borrow(), &mut state.alt_population.borrow_mut(), &selected);
+        *fitnesses = selected_fitnesses(&fitnesses, &selected);
+        *state.selected.borrow_mut() = selected;
+
+        state.population.swap(&state.alt_population);
+    });
+
+    f
+}
+
+// select with params.selection
+pub fn rgep_selection_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    rgep_select_stage(|state: &RgepState<A, B, R>| state.params.selection)
+}
+
+pub fn rgep_sus_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    rgep_select_stage(|_state: &RgepState<A, B, R>| Selection::StochasticUniversal)
+}
+
+pub fn rgep_tournament_stage<A, B, R>(tourn_size: usize, prob: f64) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    rgep_select_stage(move |_state: &RgepState<A, B, R>| Selection::Tournament { size: tourn_size, prob })
+}
+
+pub fn rgep_linear_rank_stage<A, B, R>(pressure: f64) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    rgep_select_stage(move |_state: &RgepState<A, B, R>| Selection::LinearRank { pressure })
+}
+
+pub fn rgep_exponential_rank_stage<A, B, R>(base: f64) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    rgep_select_stage(move |_state: &RgepState<A, B, R>| Selection::ExponentialRank { base })
+}
+
+// scale the fitnesses in place with params.scaling, to be run before selection
+pub fn rgep_scaling_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    scaling_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        ScalingState::new(state.fitnesses.clone(), state.params.scaling)
+    }))
+}
+
+// share the fitnesses in place with params.niching, to be run before scaling and selection
+pub fn rgep_sharing_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    sharing_stage(Rc::new(|state: &RgepState<A, B, R>| {
+        SharingState::new(state.population.clone(), state.fitnesses.clone(), state.params.niching, state.bits_per_sym)
+    }))
+}
+
+// reinsert params.hall_of_fame_elites members of the hall of fame with rgep_reinsert, to be
+// run after variation and before evaluation
+pub fn rgep_reinsert_stage<A, B, R>() -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + 'static {
+    let f: Rc<dyn Fn(&RgepState<A, B, R>, &mut R)> = Rc::new(|state, _rng| {
+        rgep_reinsert(&state.hall_of_fame.borrow(),
+                      &mut state.population.borrow_mut(),
+                      &mut state.parent_fitnesses.borrow_mut(),
+                      &mut state.rates.borrow_mut(),
+                      state.params.hall_of_fame_elites);
+    });
+
+    f
+}
+
+// The variation and evaluation of a generation of rgep with the given params, as stages in
+// the same order as rgep_vary: rotation, any transposition with a nonzero probability, point
+// mutation, and crossover between mates, with length mutation and length crossover for
+// variable-length individuals, followed by reinsertion of the hall of fame and evaluation.
+pub fn rgep_default_pipeline<A, B, R>(params: &RgepParams) -> Stage<RgepState<A, B, R>, R>
+    where A: Clone + 'static, B: Clone + 'static, R: Rng + SeedableRng + 'static {
+    let mut variation = rgep_rotation_stage();
+    if params.transposition.prob_is > 0.0 {
+        variation = compose_stages(variation, rgep_is_transposition_stage());
+    }
+    if params.transposition.prob_ris > 0.0 {
+        variation = compose_stages(variation, rgep_ris_transposition_stage());
+    }
+    if params.transposition.prob_gene > 0.0 {
+        variation = compose_stages(variation, rgep_gene_transposition_stage());
+    }
+    variation = compose_stages(variation, rgep_adaptive_mutation_stage());
+
+    let crossover = match params.variable_length {
+        Some(length) => {
+            variation = compose_stages(variation, rgep_length_mutation_stage(length));
+            rgep_length_crossover_stage(length)
+        },
+
+        None => {
+            compose_stages(rgep_crossover_one_point_stage(), rgep_crossover_two_point_stage())
+        },
+    };
+
+    let variation = compose_stages(variation, rgep_mating_stage(crossover));
+    compose_stages(compose_stages(variation, rgep_reinsert_stage()), rgep_eval_stage())
+}
+
+// Run RGEP from stages. Each generation runs pipeline, which must leave the
+// fitnesses of the population filled in, and then selection, which must leave
+// the next generation in the population. The fitnesses are converted by
+// params.objective in between, but any sharing or scaling must be part of selection,
+// as in compose_stages(rgep_scaling_stage(), rgep_sus_stage()). The hall of fame is
+// updated after each evaluation, and its elites are only reinserted by a pipeline that
+// includes rgep_reinsert_stage. The mutation rate follows params.mutation_rate's schedule.
+// The 1/5th rule and self-adaptive rates need each individual's parent, so with them the
+// selection must be one of the rgep selection stages, which record the selected individuals.
+pub fn rgep_staged<A, B, R>(params: &RgepParams,
+                            context: Rc<Context<A, B>>,
+                            state: B,
+                            eval: Rc<EvalFunction<A, B, R>>,
+                            pipeline: Stage<RgepState<A, B, R>, R>,
+                            selection: Stage<RgepState<A, B, R>, R>,
+                            observer: &mut dyn Observer<u8>) -> RgepResult
+    where A: Clone + 'static,
+          B: Clone + 'static,
+          R: Rng + SeedableRng + 'static {
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
+    let rgep_state = RgepState::create_rgep_state(params, context, state, eval, rng);
+
+    let mut best = rgep_state.population.borrow().0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+
+    let mut progress = Progress::new();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        rgep_state.rates.borrow_mut().start_generation(progress.generation, params.num_gens);
+        pipeline(&rgep_state, rng);
+
+        let control;
+        {
+            let pop = rgep_state.population.borrow();
+            let mut fitnesses = rgep_state.fitnesses.borrow_mut();
+            params.objective.convert(&mut fitnesses);
+
+            let generation = progress.generation;
+            if generation > 0 {
+                let successes = count_successes(&fitnesses, &rgep_state.parent_fitnesses.borrow());
+                rgep_state.rates.borrow_mut().record_successes(successes, fitnesses.len());
+            }
+
+            let index_fittest = fittest(&fitnesses);
+            fitness_history.push(fitnesses[index_fittest]);
+            let improved = progress.update(fitnesses[index_fittest], fitnesses.len());
+            rgep_state.hall_of_fame.borrow_mut().update(&pop, &fitnesses, generation);
+
+            control = observer.on_generation(generation, &pop, &fitnesses);
+            if improved {
+                best = pop.0[index_fittest].clone();
+                observer.on_new_best(generation, &best, progress.best_fitness);
+            }
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+
+        let fitnesses = rgep_state.fitnesses.borrow().clone();
+        rgep_state.selected.borrow_mut().clear();
+        selection(&rgep_state, rng);
+
+        let selected = rgep_state.selected.borrow();
+        if selected.is_empty() {
+            assert!(!matches!(params.mutation_rate, MutationRate::OneFifth { .. } | MutationRate::SelfAdaptive { .. }),
+                    "The 1/5th rule and self-adaptive rates need a selection stage that records the selected individuals!");
+            *rgep_state.parent_fitnesses.borrow_mut() = rgep_state.fitnesses.borrow().clone();
+        } else {
+            *rgep_state.parent_fitnesses.borrow_mut() = selected_fitnesses(&fitnesses, &selected);
+            rgep_state.rates.borrow_mut().select(&selected);
+        }
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    let population = rgep_state.population.borrow().clone();
+    let hall_of_fame = rgep_state.hall_of_fame.borrow().clone();
+    RgepResult {
+        best_string: rgep_state.context.to_string(&best),
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        population,
+        hall_of_fame,
+    }
+}
+
+#[test]
+fn test_rgep_staged_matches_rgep() {
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        num_gens: 10,
+        seed: 99,
+        ..RgepParams::default()
+    };
+
+    let eval_prog: Rc<EvalFunction<f64, (), R>> = Rc::new(distance_to_five);
+
+    let expected = rgep(&params, &arith_context(), &(), &*eval_prog);
+
+    let result = rgep_staged(&params,
+                             Rc::new(arith_context()),
+                             (),
+                             eval_prog.clone(),
+                             rgep_default_pipeline(&params),
+                             rgep_sus_stage(),
+                             &mut NullObserver);
+
+    assert_eq!(result.population, expected.population);
+    assert_eq!(result.fitness_history, expected.fitness_history);
+
+    // variable-length individuals are varied with the length operators in both
+    let params = RgepParams {
+        variable_length: Some(LengthParams { min_size: 3, max_size: 15, prob_insertion: 0.2, prob_deletion: 0.2, ..LengthParams::default() }),
+        cut_mode: CutMode::Symbol,
+        ..params
+    };
+
+    let expected = rgep(&params, &arith_context(), &(), &*eval_prog);
+
+    let result = rgep_staged(&params,
+                             Rc::new(arith_context()),
+                             (),
+                             eval_prog.clone(),
+                             rgep_default_pipeline(&params),
+                             rgep_sus_stage(),
+                             &mut NullObserver);
+
+    assert!(result.population.0.iter().any(|ind| ind.0.len() != params.ind_size));
+    assert_eq!(result.population, expected.population);
+    assert_eq!(result.fitness_history, expected.fitness_history);
+
+    // scheduled mutation rates change in the same way in both, as does transposition
+    let params = RgepParams {
+        prob_mut: 0.2,
+        mutation_rate: MutationRate::Exponential { decay: 0.5 },
+        variable_length: None,
+        transposition: TranspositionParams { prob_is: 0.5, prob_ris: 0.5, prob_gene: 0.5, max_length: 3, gene_size: 5 },
+        ..params
+    };
+
+    let expected = rgep(&params, &arith_context(), &(), &*eval_prog);
+
+    let result = rgep_staged(&params,
+                             Rc::new(arith_context()),
+                             (),
+                             eval_prog.clone(),
+                             rgep_default_pipeline(&params),
+                             rgep_sus_stage(),
+                             &mut NullObserver);
+
+    assert_eq!(result.population, expected.population);
+    assert_eq!(result.fitness_history, expected.fitness_history);
+}
+
+#[test]
+fn test_rgep_staged_adaptive_rates() {
+    let eval_prog: Rc<EvalFunction<f64, (), R>> = Rc::new(distance_to_five);
+
+    // the 1/5th rule and self-adaptive rates follow each individual's parent as in rgep,
+    // including through mating and reinsertion of the hall of fame
+    for mutation_rate in [MutationRate::OneFifth { factor: 1.5 }, MutationRate::SelfAdaptive { learning_rate: 0.5 }] {
+        let params = RgepParams {
+            pop_size: 20,
+            ind_size: 10,
+            num_gens: 10,
+            seed: 99,
+            prob_mut: 0.1,
+            mutation_rate,
+            mating: Mating::Tournament { size: 2 },
+            hall_of_fame_size: 2,
+            hall_of_fame_elites: 2,
+            ..RgepParams::default()
+        };
+
+        let expected = rgep(&params, &arith_context(), &(), &*eval_prog);
+
+        let result = rgep_staged(&params,
+                                 Rc::new(arith_context()),
+                                 (),
+                                 eval_prog.clone(),
+                                 rgep_default_pipeline(&params),
+                                 rgep_selection_stage(),
+                                 &mut NullObserver);
+
+        assert_eq!(result.population, expected.population);
+        assert_eq!(result.fitness_history, expected.fitness_history);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_rgep_staged_self_adaptive_rates_need_selected() {
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        mutation_rate: MutationRate::SelfAdaptive { learning_rate: 0.5 },
+        ..RgepParams::default()
+    };
+
+    let eval_prog: Rc<EvalFunction<f64, (), R>> = Rc::new(distance_to_five);
+
+    let selection: Stage<RgepState<f64, (), R>, R> = sus_stage(Rc::new(|state: &RgepState<f64, (), R>| {
+        return SusState::new(state.population.clone(), state.alt_population.clone(), state.fitnesses.clone(), state.params.elitism);
+    }));
+
+    rgep_staged(&params, Rc::new(arith_context()), (), eval_prog, rgep_default_pipeline(&params), selection, &mut NullObserver);
+}
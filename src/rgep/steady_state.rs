@@ -0,0 +1,153 @@
+use rand::prelude::*;
+
+use crate::types::*;
+use crate::selection::*;
+use crate::evaluation::*;
+use crate::replacement::*;
+use crate::termination::*;
+use crate::observer::*;
+use crate::hall_of_fame::*;
+use crate::adaptive::*;
+
+use rgep::*;
+use rgep::context::*;
+
+
+// Steady-state RGEP. Each step chooses params.num_offspring parents by binary
+// tournament, varies copies of them with the usual operators, evaluates only
+// those offspring, and places them into the population using params.replacement.
+// Each step counts as one generation for termination and the fitness history.
+pub fn rgep_steady_state<R, A, B>(params: &RgepParams,
+                                  context: &Context<A, B>,
+                                  state: &B,
+                                  eval_ind: &EvalFunction<A, B, R>) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    rgep_steady_state_with_observer(params, context, state, eval_ind, &mut NullObserver)
+}
+
+pub fn rgep_steady_state_with_observer<R, A, B>(params: &RgepParams,
+                                                context: &Context<A, B>,
+                                                state: &B,
+                                                eval_ind: &EvalFunction<A, B, R>,
+                                                observer: &mut dyn Observer<u8>) -> RgepResult
+    where R: Rng + SeedableRng, A: Clone, B: Clone {
+    assert!(params.hall_of_fame_elites == 0, "Hall of fame elites are only reinserted by generational runs, not steady-state runs!");
+
+    let mut rng = R::seed_from_u64(params.seed);
+    let rng = &mut rng;
+
+    let mut pop = create_rgep(params, context, rng);
+    let mut fitnesses = rgep_evaluate(&pop, context, state, eval_ind, rng);
+    params.objective.convert(&mut fitnesses);
+
+    let bits_per_sym = context.bits_per_sym();
+
+    let mut offspring = Pop(Vec::with_capacity(params.num_offspring));
+    let mut parents = Vec::with_capacity(params.num_offspring);
+    let replacement = ReplacementParams { policy: params.replacement, elitism: params.elitism, bits_used: bits_per_sym };
+
+    let mut best = pop.0[0].clone();
+    let mut fitness_history = Vec::with_capacity(params.num_gens);
+    let mut hall_of_fame = HallOfFame::new(params.hall_of_fame_size);
+    let mut rates = rgep_mutation_rates(params, params.pop_size);
+
+    let mut progress = Progress::new();
+    progress.evaluations += fitnesses.len();
+
+    observer.on_start();
+
+    let stop_reason;
+    loop {
+        if let Some(reason) = params.termination.check(params.num_gens, &progress) {
+            stop_reason = reason;
+            break;
+        }
+
+        offspring.0.clear();
+        parents.clear();
+        for _ in 0..params.num_offspring {
+            let parent_index = tournament_index(&fitnesses, 2, rng);
+            offspring.0.push(pop.0[parent_index].clone());
+            parents.push(parent_index);
+        }
+
+        // mating may rearrange the offspring, so their parents are rearranged to match
+        rates.start_generation(progress.generation, params.num_gens);
+        let mut offspring_rates = rates.clone();
+        offspring_rates.select(&parents);
+        let parent_fitnesses = selected_fitnesses(&fitnesses, &parents);
+        let order = rgep_vary(&mut offspring, &parent_fitnesses, &mut offspring_rates, params, bits_per_sym, rng);
+        parents = order.iter().map(|index| parents[*index]).collect();
+
+        let mut offspring_fitnesses = rgep_evaluate(&offspring, context, state, eval_ind, rng);
+        params.objective.convert(&mut offspring_fitnesses);
+        rates.record_successes(count_successes(&offspring_fitnesses, &selected_fitnesses(&parent_fitnesses, &order)), offspring_fitnesses.len());
+        hall_of_fame.update(&offspring, &offspring_fitnesses, progress.generation);
+
+        let placed = replace_offspring(replacement, &mut pop, &mut fitnesses, &mut offspring, &offspring_fitnesses, &parents, rng);
+
+        // the offspring that entered the population bring their rates with them
+        if !rates.rates.is_empty() {
+            for (child_index, index) in placed {
+                rates.rates[index] = offspring_rates.rates[child_index];
+            }
+        }
+
+        let generation = progress.generation;
+        let index_fittest = fittest(&fitnesses);
+        fitness_history.push(fitnesses[index_fittest]);
+        let improved = progress.update(fitnesses[index_fittest], offspring_fitnesses.len());
+
+        let control = observer.on_generation(generation, &pop, &fitnesses);
+        if improved {
+            best = pop.0[index_fittest].clone();
+            observer.on_new_best(generation, &best, progress.best_fitness);
+        }
+
+        if control == Control::Stop {
+            stop_reason = StopReason::Observer;
+            break;
+        }
+    }
+
+    observer.on_end(stop_reason, &best, progress.best_fitness);
+
+    RgepResult {
+        best_string: context.to_string(&best),
+        best,
+        best_fitness: progress.best_fitness,
+        fitness_history,
+        stop_reason,
+        population: pop,
+        hall_of_fame,
+    }
+}
+
+#[test]
+fn test_rgep_steady_state_counts_evaluations() {
+    let context = arith_context();
+
+    let params = RgepParams {
+        pop_size: 20,
+        ind_size: 10,
+        num_offspring: 2,
+        replacement: ReplacementPolicy::InverseTournament(3),
+        termination: Termination::new().with(Criterion::MaxEvaluations(20 + 2 * 15)),
+        ..RgepParams::default()
+    };
+
+    let eval_prog: &EvalFunction<f64, (), R> = &distance_to_five;
+
+    let result = rgep_steady_state(&params, &context, &(), eval_prog);
+
+    assert_eq!(result.stop_reason, StopReason::MaxEvaluations);
+    assert_eq!(result.fitness_history.len(), 15);
+
+    // crowding never replaces an individual with a less fit one, so the best never gets worse
+    let params = RgepParams {
+        replacement: ReplacementPolicy::DeterministicCrowding,
+        ..params
+    };
+    let result = rgep_steady_state(&params, &context, &(), eval_prog);
+    assert!(result.fitness_history.windows(2).all(|pair| pair[0] <= pair[1]));
+}
@@ -1,28 +1,91 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
 use rand::prelude::*;
 
 use statrs::distribution::Uniform;
 
+use num::PrimInt;
+
 use types::*;
+use stage::*;
+use crossover::{CutMode, low_bit_mask};
 
 
-pub fn rotation<T, R>(pop: &mut Pop<T>, pr: f64, rng: &mut R) 
-    where T: Copy ,
-          R: Rng {
-    let ind_len = pop.0[0].0.len();
+// pr is the probability of rotating each individual, by an amount chosen where cut_mode allows
+pub struct RotationState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub pr: f64,
+    pub bits_used: usize,
+    pub cut_mode: CutMode,
+}
+
+impl<T> RotationState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               pr: f64,
+               bits_used: usize,
+               cut_mode: CutMode) -> RotationState<T> {
+        RotationState { population, pr, bits_used, cut_mode }
+    }
+}
+
+pub fn rotation_stage<S, T, R>(getter: Getter<S, RotationState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: PrimInt + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let rotation_state = getter(state);
+        rotation(&mut rotation_state.population.borrow_mut(),
+                 rotation_state.bits_used,
+                 rotation_state.cut_mode,
+                 rotation_state.pr,
+                 rng);
+    });
+
+    f
+}
 
+// Rotate each individual with probability pr. With CutMode::Symbol individuals rotate by
+// whole words, and with CutMode::Bit they rotate through their used bits.
+pub fn rotation<T, R>(pop: &mut Pop<T>, bits_per_sym: usize, cut_mode: CutMode, pr: f64, rng: &mut R) 
+    where T: PrimInt,
+          R: Rng {
     let rotation_sampler = Uniform::new(0.0, 1.0).unwrap();
-    let rotation_point_sampler = Uniform::new(0.0, ind_len as f64).unwrap();
 
     let mut scratch = Vec::new();
 
     for ind in pop.0.iter_mut() {
         if rotation_sampler.sample(rng) < pr {
-            let rotation_point = rotation_point_sampler.sample(rng) as usize;
-            rotate_copy(ind, &mut scratch, rotation_point);
+            // individuals may differ in length, so each is rotated within its own length
+            let num_cut_points = cut_mode.num_cut_points(ind.0.len(), bits_per_sym);
+            let rotation_point = Uniform::new(0.0, num_cut_points as f64).unwrap().sample(rng) as usize;
+            match cut_mode {
+                CutMode::Symbol => rotate_copy(ind, &mut scratch, rotation_point),
+                CutMode::Bit => rotate_bits(ind, &mut scratch, bits_per_sym, rotation_point),
+            }
         }
     }
 }
 
+// Rotate the used bits of an individual, as if they were one string of bits with
+// bits_per_sym bits from each word, so that bit index of the result is bit
+// index + rotation_point of the original. The unused bits of each word are left in place.
+pub fn rotate_bits<T: PrimInt>(ind: &mut Ind<T>, scratch: &mut Vec<T>, bits_per_sym: usize, rotation_point: usize) {
+    let num_bits = ind.0.len() * bits_per_sym;
+
+    scratch.clear();
+    scratch.extend(ind.0.iter().map(|word| *word & !low_bit_mask::<T>(bits_per_sym)));
+
+    for bit_index in 0..num_bits {
+        let source = (bit_index + rotation_point) % num_bits;
+        let bit = (ind.0[source / bits_per_sym] >> (source % bits_per_sym)) & T::one();
+        scratch[bit_index / bits_per_sym] = scratch[bit_index / bits_per_sym] | (bit << (bit_index % bits_per_sym));
+    }
+
+    ind.0.clear();
+    ind.0.extend_from_slice(scratch);
+}
+
 pub fn rotate_naive<T>(ind: &mut Ind<T>, rotation_point: usize) 
     where T: Copy {
     let ind_len = ind.0.len();
@@ -85,3 +148,15 @@ fn test_rotate_copy() {
     assert!(ind == expected, format!("{:?} != {:?}", ind, expected));
 }
 
+
+#[test]
+fn test_rotate_bits() {
+    // the low three bits of each word are used, and the high bit is set but unused. From
+    // the lowest bit, the used bits are 100 110 111, and rotating by 4 gives 101 111 001.
+    let mut ind = Ind(vec!(0x81u8, 0x83, 0x87));
+    let mut scratch = Vec::new();
+
+    rotate_bits(&mut ind, &mut scratch, 3, 4);
+
+    assert_eq!(ind, Ind(vec!(0x85u8, 0x87, 0x84)));
+}
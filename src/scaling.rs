@@ -0,0 +1,226 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use rand::prelude::*;
+
+use stage::*;
+
+
+// Whether the evaluation function's values should be maximized or minimized.
+// Minimized values (such as errors, which should not be negative) are converted
+// with 1 / (1 + error), so the best fitness reported by a run is also converted.
+// Negative errors are clamped to 0 first, so they all convert to a fitness of 1, and NaN
+// errors are the worst, converting to a fitness of 0.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Objective {
+    #[default]
+    Maximize,
+    Minimize,
+}
+
+impl Objective {
+    pub fn convert(&self, fitnesses: &mut [f64]) {
+        if *self == Objective::Minimize {
+            minimize_to_maximize(fitnesses);
+        }
+    }
+}
+
+// Transforms applied to fitnesses just before selection. Unlike the objective
+// conversion, these depend on the whole population, so they only affect selection
+// and not the fitness values a run reports.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum FitnessScaling {
+    #[default]
+    None,
+    Linear { multiple: f64 },
+    SigmaTruncation { c: f64 },
+    Window,
+    Boltzmann { temperature: f64 },
+}
+
+impl FitnessScaling {
+    // Scale the fitnesses in place. Other than FitnessScaling::None, every scaling leaves
+    // non-negative values with a positive total, falling back to equal fitnesses when
+    // the population gives nothing to choose between.
+    pub fn apply(&self, fitnesses: &mut [f64]) {
+        match self {
+            FitnessScaling::None => {
+                return;
+            },
+
+            FitnessScaling::Linear { multiple } => {
+                linear_scaling(fitnesses, *multiple);
+            },
+
+            FitnessScaling::SigmaTruncation { c } => {
+                sigma_truncation(fitnesses, *c);
+            },
+
+            FitnessScaling::Window => {
+                window_scaling(fitnesses);
+            },
+
+            FitnessScaling::Boltzmann { temperature } => {
+                boltzmann_scaling(fitnesses, *temperature);
+            },
+        }
+
+        let total = fitnesses.iter().sum::<f64>();
+        if total.is_nan() || total.is_infinite() || total <= 0.0 {
+            for fitness in fitnesses.iter_mut() {
+                *fitness = 1.0;
+            }
+        }
+    }
+}
+
+// convert errors to fitnesses in [0, 1], treating negative errors as 0 and NaN errors as
+// infinite
+pub fn minimize_to_maximize(fitnesses: &mut [f64]) {
+    for fitness in fitnesses.iter_mut() {
+        *fitness = if fitness.is_nan() { 0.0 } else { 1.0 / (1.0 + fitness.max(0.0)) };
+    }
+}
+
+fn mean_and_max(fitnesses: &[f64]) -> (f64, f64) {
+    let mean = fitnesses.iter().sum::<f64>() / fitnesses.len() as f64;
+    let max = fitnesses.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    (mean, max)
+}
+
+// Goldberg's linear scaling. The mean is kept, and the fittest individual is scaled
+// to multiple times the mean. If that would make any fitness negative, the scaling is
+// reduced so that the least fit individual scales to 0.
+pub fn linear_scaling(fitnesses: &mut [f64], multiple: f64) {
+    assert!(multiple > 1.0, "Linear scaling multiple must be greater than 1, but was {}", multiple);
+
+    let (mean, max) = mean_and_max(fitnesses);
+    let min = fitnesses.iter().cloned().fold(f64::INFINITY, f64::min);
+
+    if max == mean {
+        return;
+    }
+
+    let (a, b);
+    if min > (multiple * mean - max) / (multiple - 1.0) {
+        a = (multiple - 1.0) * mean / (max - mean);
+        b = mean * (max - multiple * mean) / (max - mean);
+    } else {
+        a = mean / (mean - min);
+        b = -min * mean / (mean - min);
+    }
+
+    for fitness in fitnesses.iter_mut() {
+        *fitness = (a * *fitness + b).max(0.0);
+    }
+}
+
+// subtract c standard deviations below the mean, dropping negative results to 0
+pub fn sigma_truncation(fitnesses: &mut [f64], c: f64) {
+    let (mean, _max) = mean_and_max(fitnesses);
+    let variance = fitnesses.iter().map(|fitness| (fitness - mean).powi(2)).sum::<f64>() / fitnesses.len() as f64;
+    let offset = mean - c * variance.sqrt();
+
+    for fitness in fitnesses.iter_mut() {
+        *fitness = (*fitness - offset).max(0.0);
+    }
+}
+
+// subtract the least fitness in the population
+pub fn window_scaling(fitnesses: &mut [f64]) {
+    let min = fitnesses.iter().cloned().fold(f64::INFINITY, f64::min);
+
+    for fitness in fitnesses.iter_mut() {
+        *fitness -= min;
+    }
+}
+
+// Boltzmann (softmax) scaling, exp(fitness / temperature). The values are shifted by the
+// maximum fitness first so that the exponential cannot overflow.
+pub fn boltzmann_scaling(fitnesses: &mut [f64], temperature: f64) {
+    assert!(temperature > 0.0, "Boltzmann temperature must be positive, but was {}", temperature);
+
+    let (_mean, max) = mean_and_max(fitnesses);
+
+    for fitness in fitnesses.iter_mut() {
+        *fitness = ((*fitness - max) / temperature).exp();
+    }
+}
+
+pub struct ScalingState {
+    pub fitnesses: Rc<RefCell<Vec<f64>>>,
+    pub scaling: FitnessScaling,
+}
+
+impl ScalingState {
+    pub fn new(fitnesses: Rc<RefCell<Vec<f64>>>,
+               scaling: FitnessScaling) -> ScalingState {
+        ScalingState { fitnesses, scaling }
+    }
+}
+
+// scale the fitnesses in place, for use between evaluation and selection
+pub fn scaling_stage<S, R>(getter: Getter<S, ScalingState>) -> Stage<S, R>
+    where R: Rng,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, _rng| {
+        let scaling_state = getter(state);
+        scaling_state.scaling.apply(&mut scaling_state.fitnesses.borrow_mut());
+    });
+
+    f
+}
+
+#[test]
+fn test_fitness_scaling() {
+    use float_cmp::ApproxEq;
+
+    let mut fitnesses = vec!(1.0, 2.0, 3.0, 6.0);
+    FitnessScaling::Linear { multiple: 2.0 }.apply(&mut fitnesses);
+    assert!(fitnesses[3].approx_eq(&6.0, 2.0 * f64::EPSILON, 2), "{:?}", fitnesses);
+    assert!((fitnesses.iter().sum::<f64>() / 4.0).approx_eq(&3.0, 2.0 * f64::EPSILON, 2), "{:?}", fitnesses);
+
+    // with every fitness equal there is nothing to scale
+    let mut fitnesses = vec!(2.0, 2.0, 2.0);
+    FitnessScaling::Linear { multiple: 2.0 }.apply(&mut fitnesses);
+    assert_eq!(fitnesses, vec!(2.0, 2.0, 2.0));
+
+    let mut fitnesses = vec!(-3.0, -1.0, 1.0);
+    FitnessScaling::Window.apply(&mut fitnesses);
+    assert_eq!(fitnesses, vec!(0.0, 2.0, 4.0));
+
+    // nothing to choose between, so every individual is equally likely
+    let mut fitnesses = vec!(-2.0, -2.0, -2.0);
+    FitnessScaling::Window.apply(&mut fitnesses);
+    assert_eq!(fitnesses, vec!(1.0, 1.0, 1.0));
+
+    let mut fitnesses = vec!(1000.0, 999.0);
+    FitnessScaling::Boltzmann { temperature: 1.0 }.apply(&mut fitnesses);
+    assert_eq!(fitnesses[0], 1.0);
+    assert!(fitnesses[1] > 0.0 && fitnesses[1] < 1.0);
+
+    let mut fitnesses = vec!(1.0, 2.0, 3.0);
+    FitnessScaling::SigmaTruncation { c: 1.0 }.apply(&mut fitnesses);
+    assert!(fitnesses.iter().all(|fitness| *fitness >= 0.0));
+    assert!(fitnesses[0] < fitnesses[1] && fitnesses[1] < fitnesses[2]);
+
+    let mut errors = vec!(0.0, 1.0, 3.0);
+    Objective::Minimize.convert(&mut errors);
+    assert_eq!(errors, vec!(1.0, 0.5, 0.25));
+
+    let mut errors = vec!(-2.0, -0.5, 0.0);
+    Objective::Minimize.convert(&mut errors);
+    assert_eq!(errors, vec!(1.0, 1.0, 1.0));
+
+    // a NaN error is the worst, rather than clamped to the best
+    let mut errors = vec!(f64::NAN, 1.0, f64::INFINITY);
+    Objective::Minimize.convert(&mut errors);
+    assert_eq!(errors, vec!(0.0, 0.5, 0.0));
+}
+
+#[test]
+#[should_panic]
+fn test_linear_scaling_multiple() {
+    linear_scaling(&mut vec!(1.0, 2.0, 3.0), 1.0);
+}
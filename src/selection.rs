@@ -3,8 +3,6 @@ use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::iter;
 
-use num::traits::Pow;
-
 use rand::prelude::*;
 use rand::distributions::Distribution;
 
@@ -14,19 +12,15 @@ use types::*;
 use stage::*;
 
 
-pub fn k_elite(fitnesses: &Vec<f64>, num_elite: usize) -> Vec<usize> {
+pub fn k_elite(fitnesses: &[f64], num_elite: usize) -> Vec<usize> {
     // elitism- give a certain number of individuals a free pass to the next generation
     let mut elite_indices: Vec<usize> = Vec::new();
     if num_elite > 0 {
         // pair fitnesses with indices
         let mut elite_paired: Vec<(usize, &f64)> = fitnesses.iter().enumerate().collect();
-        // sort paired fitnesses by fitness
-        elite_paired .sort_unstable_by(|(_index, fitness), (_index_other, fitness_other)| {
-            if fitness > fitness_other {
-                Ordering::Less
-            } else if fitness > fitness_other {
-                Ordering::Greater
-            } else { Ordering::Equal }
+        // sort paired fitnesses by fitness, fittest first
+        elite_paired.sort_by(|(_index, fitness), (_index_other, fitness_other)| {
+            fitness_other.partial_cmp(fitness).unwrap_or(Ordering::Equal)
         });
         // add the k most elite individual's indices to the elite_indices vec
         elite_indices.extend(elite_paired.iter().take(num_elite).map(|(index, _)| index));
@@ -35,57 +29,350 @@ pub fn k_elite(fitnesses: &Vec<f64>, num_elite: usize) -> Vec<usize> {
     elite_indices
 }
 
-pub fn tournament_selection<R: Rng, T: Copy>(pop: &Pop<T>, new_pop: &mut Pop<T>, fitnesses: Vec<f64>, prob: f64, tourn_size: usize, elitism: usize, rng: &mut R) {
-    let winner_rng = Uniform::new(0.0, 1.0).unwrap();
+// index of the fittest of tourn_size individuals chosen uniformly with replacement
+pub fn tournament_index<R: Rng>(fitnesses: &[f64], tourn_size: usize, rng: &mut R) -> usize {
+    let mut best_index = rng.gen_range(0, fitnesses.len());
+    for _ in 1..tourn_size {
+        let index = rng.gen_range(0, fitnesses.len());
+        if fitnesses[index] > fitnesses[best_index] {
+            best_index = index;
+        }
+    }
 
-    let num_inds = pop.0.len();
+    best_index
+}
 
-    let mut elite_indices = k_elite(&fitnesses, elitism);
+// Stochastic tournament selection with replacement. Each tournament draws tourn_size
+// individuals, and the one with rank k (the fittest has rank 0) wins with probability
+// prob * (1 - prob)^k, with the last rank taking whatever probability remains.
+// The elitism fittest individuals are copied into the start of new_pop first.
+pub fn tournament_selection<R: Rng, T: Copy>(pop: &Pop<T>, new_pop: &mut Pop<T>, fitnesses: &[f64], prob: f64, tourn_size: usize, elitism: usize, rng: &mut R) {
+    let selected = tournament_indices(fitnesses, new_pop.0.len(), prob, tourn_size, elitism, rng);
+    copy_selected(pop, new_pop, &selected);
+}
 
-    let mut num_selections = 0;
+pub fn tournament_indices<R: Rng>(fitnesses: &[f64], num_select: usize, prob: f64, tourn_size: usize, elitism: usize, rng: &mut R) -> Vec<usize> {
+    assert!(tourn_size > 0, "Tournaments must have at least one individual!");
 
-    let prob_indices = (0..tourn_size).map(|_index| prob * (1.0 - prob).pow(tourn_size as i32)).collect::<Vec<f64>>();
+    let num_inds = fitnesses.len();
 
-    while num_selections < num_inds {
-        // NOTE consider re-using this vector. benchmark for comparison
-        let mut tourny = iter::repeat_with(|| {
-            let index = rng.gen_range(0, pop.0[0].0.len());
-            let ind = &pop.0[index];
-            let fitness = fitnesses[index];
-            (ind, fitness)
-        }).collect::<Vec<(&Ind<T>, f64)>>();
+    let mut selected = k_elite(fitnesses, elitism);
+    selected.truncate(num_select);
+
+    // NOTE consider re-using this vector. benchmark for comparison
+    let mut tourny = Vec::with_capacity(tourn_size);
+
+    while selected.len() < num_select {
+        tourny.clear();
+        tourny.extend(iter::repeat_with(|| rng.gen_range(0, num_inds)).take(tourn_size));
 
-        tourny.sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        tourny.sort_unstable_by(|index, other| fitnesses[*other].partial_cmp(&fitnesses[*index]).unwrap_or(Ordering::Equal));
 
-        let pos_rng = rng.sample(winner_rng);
+        let winner = tourny[..tourn_size - 1].iter()
+                                              .find(|_| rng.gen::<f64>() < prob)
+                                              .cloned()
+                                              .unwrap_or(tourny[tourn_size - 1]);
 
-        let winner_index = prob_indices.iter().position(|p| pos_rng <= *p).unwrap();
+        selected.push(winner);
+    }
+
+    selected
+}
+
+// the fitness of each selected individual, in the order they were selected
+pub fn selected_fitnesses(fitnesses: &[f64], selected: &[usize]) -> Vec<f64> {
+    selected.iter().map(|index| fitnesses[*index]).collect()
+}
+
+// new_pop[index] becomes a copy of pop[selected[index]]
+pub fn copy_selected<T: Copy>(pop: &Pop<T>, new_pop: &mut Pop<T>, selected: &[usize]) {
+    for (new_ind, selected_index) in new_pop.0.iter_mut().zip(selected.iter()) {
+        new_ind.0.clear();
+        new_ind.0.extend(pop.0[*selected_index].0.iter());
+    }
+}
+
+pub struct TournamentState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub alt_population: Rc<RefCell<Pop<T>>>,
+    pub fitnesses: Rc<RefCell<Vec<f64>>>,
+    pub prob: f64,
+    pub tourn_size: usize,
+    pub elitism: usize,
+}
 
-        if let Some(elite_index) = elite_indices.iter().position(|a| *a == winner_index) {
-            elite_indices.swap_remove(elite_index);
+impl<T> TournamentState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               alt_population: Rc<RefCell<Pop<T>>>,
+               fitnesses: Rc<RefCell<Vec<f64>>>,
+               prob: f64,
+               tourn_size: usize,
+               elitism: usize) -> TournamentState<T> {
+        TournamentState {
+            population,
+            alt_population,
+            fitnesses,
+            prob,
+            tourn_size,
+            elitism,
         }
+    }
+}
 
-        num_selections += 1;
+// Select into the alternate population, then swap so that the population
+// holds the next generation.
+pub fn tournament_stage<S, T, R>(getter: Getter<S, TournamentState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: Copy + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let tourn_state = getter(state);
+        tournament_selection(&tourn_state.population.borrow(),
+                             &mut tourn_state.alt_population.borrow_mut(),
+                             &tourn_state.fitnesses.borrow(),
+                             tourn_state.prob,
+                             tourn_state.tourn_size,
+                             tourn_state.elitism,
+                             rng);
+        tourn_state.population.swap(&tourn_state.alt_population);
+    });
+
+    f
+}
+
+// indices of individuals ordered from least to most fit
+pub fn rank_order(fitnesses: &[f64]) -> Vec<usize> {
+    let mut order = (0..fitnesses.len()).collect::<Vec<usize>>();
+    order.sort_by(|index, other| fitnesses[*index].partial_cmp(&fitnesses[*other]).unwrap_or(Ordering::Equal));
+    order
+}
+
+// Linear ranking weights. The least fit individual gets (2 - pressure) / n and the fittest
+// pressure / n, with pressure between 1.0 (no selection pressure) and 2.0.
+pub fn linear_rank_weights(fitnesses: &[f64], pressure: f64) -> Vec<f64> {
+    assert!((1.0..=2.0).contains(&pressure), "Linear ranking pressure must be in [1, 2], but was {}", pressure);
+
+    let num_inds = fitnesses.len() as f64;
+    let mut weights = vec![0.0; fitnesses.len()];
+
+    for (rank, index) in rank_order(fitnesses).iter().enumerate() {
+        let slope = if num_inds > 1.0 { (rank as f64) / (num_inds - 1.0) } else { 1.0 };
+        weights[*index] = ((2.0 - pressure) + 2.0 * (pressure - 1.0) * slope) / num_inds;
+    }
+
+    weights
+}
+
+// Exponential ranking weights. The fittest individual gets weight 1.0, and each rank below
+// it is multiplied by base, with base in (0, 1). Smaller bases mean more selection pressure.
+pub fn exponential_rank_weights(fitnesses: &[f64], base: f64) -> Vec<f64> {
+    assert!(base > 0.0 && base < 1.0, "Exponential ranking base must be in (0, 1), but was {}", base);
+
+    let num_inds = fitnesses.len();
+    let mut weights = vec![0.0; num_inds];
+
+    for (rank, index) in rank_order(fitnesses).iter().enumerate() {
+        weights[*index] = base.powi((num_inds - 1 - rank) as i32);
+    }
+
+    weights
+}
+
+// Stochastic universal sampling over linear ranking weights. Elitism works as in
+// select_stochastic_universal, as ranking does not change the order of individuals.
+pub fn linear_rank_selection<R, T>(pop: &Pop<T>, new_pop: &mut Pop<T>, fitnesses: &[f64], pressure: f64, elitism: usize, rng: &mut R)
+    where T: Copy,
+          R: Rng {
+    let weights = linear_rank_weights(fitnesses, pressure);
+    stochastic_universal_sampling(pop, new_pop, &weights, elitism, rng);
+}
+
+pub fn exponential_rank_selection<R, T>(pop: &Pop<T>, new_pop: &mut Pop<T>, fitnesses: &[f64], base: f64, elitism: usize, rng: &mut R)
+    where T: Copy,
+          R: Rng {
+    let weights = exponential_rank_weights(fitnesses, base);
+    stochastic_universal_sampling(pop, new_pop, &weights, elitism, rng);
+}
+
+// pressure is the linear ranking pressure or the exponential ranking base,
+// depending on which stage uses the state.
+pub struct RankState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub alt_population: Rc<RefCell<Pop<T>>>,
+    pub fitnesses: Rc<RefCell<Vec<f64>>>,
+    pub pressure: f64,
+    pub elitism: usize,
+}
+
+impl<T> RankState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               alt_population: Rc<RefCell<Pop<T>>>,
+               fitnesses: Rc<RefCell<Vec<f64>>>,
+               pressure: f64,
+               elitism: usize) -> RankState<T> {
+        RankState {
+            population,
+            alt_population,
+            fitnesses,
+            pressure,
+            elitism,
+        }
+    }
+}
+
+// Select into the alternate population, then swap so that the population
+// holds the next generation.
+pub fn linear_rank_stage<S, T, R>(getter: Getter<S, RankState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: Copy + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let rank_state = getter(state);
+        linear_rank_selection(&rank_state.population.borrow(),
+                              &mut rank_state.alt_population.borrow_mut(),
+                              &rank_state.fitnesses.borrow(),
+                              rank_state.pressure,
+                              rank_state.elitism,
+                              rng);
+        rank_state.population.swap(&rank_state.alt_population);
+    });
+
+    f
+}
+
+pub fn exponential_rank_stage<S, T, R>(getter: Getter<S, RankState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: Copy + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let rank_state = getter(state);
+        exponential_rank_selection(&rank_state.population.borrow(),
+                                   &mut rank_state.alt_population.borrow_mut(),
+                                   &rank_state.fitnesses.borrow(),
+                                   rank_state.pressure,
+                                   rank_state.elitism,
+                                   rng);
+        rank_state.population.swap(&rank_state.alt_population);
+    });
+
+    f
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Selection {
+    #[default]
+    StochasticUniversal,
+    Tournament { size: usize, prob: f64 },
+    LinearRank { pressure: f64 },
+    ExponentialRank { base: f64 },
+}
+
+// fill new_pop from pop using the given selection scheme
+pub fn select<T, R>(selection: Selection, pop: &Pop<T>, new_pop: &mut Pop<T>, fitnesses: &Vec<f64>, elitism: usize, rng: &mut R)
+    where T: Copy,
+          R: Rng {
+    match selection {
+        Selection::StochasticUniversal => {
+            stochastic_universal_sampling(pop, new_pop, fitnesses, elitism, rng);
+        },
+
+        Selection::Tournament { size, prob } => {
+            tournament_selection(pop, new_pop, fitnesses, prob, size, elitism, rng);
+        },
+
+        Selection::LinearRank { pressure } => {
+            linear_rank_selection(pop, new_pop, fitnesses, pressure, elitism, rng);
+        },
+
+        Selection::ExponentialRank { base } => {
+            exponential_rank_selection(pop, new_pop, fitnesses, base, elitism, rng);
+        },
     }
+}
+
+// The indices of num_select individuals chosen using the given selection scheme. Unlike
+// select, this can choose a different number of individuals than there are fitnesses,
+// and lets callers carry per-individual bookkeeping along with the selected individuals.
+pub fn select_indices<R: Rng>(selection: Selection, fitnesses: &[f64], num_select: usize, elitism: usize, rng: &mut R) -> Vec<usize> {
+    match selection {
+        Selection::StochasticUniversal => {
+            let offset_scaler = Uniform::new(0.0, 1.0).unwrap().sample(rng);
+            stochastic_universal_indices(fitnesses, num_select, elitism, offset_scaler)
+        },
+
+        Selection::Tournament { size, prob } => {
+            tournament_indices(fitnesses, num_select, prob, size, elitism, rng)
+        },
+
+        Selection::LinearRank { pressure } => {
+            let offset_scaler = Uniform::new(0.0, 1.0).unwrap().sample(rng);
+            stochastic_universal_indices(&linear_rank_weights(fitnesses, pressure), num_select, elitism, offset_scaler)
+        },
+
+        Selection::ExponentialRank { base } => {
+            let offset_scaler = Uniform::new(0.0, 1.0).unwrap().sample(rng);
+            stochastic_universal_indices(&exponential_rank_weights(fitnesses, base), num_select, elitism, offset_scaler)
+        },
+    }
+}
 
-    for elite_index in elite_indices {
-        new_pop.0[elite_index].0.clear();
-        new_pop.0[elite_index].0.extend(pop.0[num_selections].0.iter());
+// Any selection scheme, through select_indices. After selecting, the fitnesses are replaced
+// by those of the selected individuals, so they stay aligned with the population.
+pub struct SelectionState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub alt_population: Rc<RefCell<Pop<T>>>,
+    pub fitnesses: Rc<RefCell<Vec<f64>>>,
+    pub selection: Selection,
+    pub elitism: usize,
+}
+
+impl<T> SelectionState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               alt_population: Rc<RefCell<Pop<T>>>,
+               fitnesses: Rc<RefCell<Vec<f64>>>,
+               selection: Selection,
+               elitism: usize) -> SelectionState<T> {
+        SelectionState {
+            population,
+            alt_population,
+            fitnesses,
+            selection,
+            elitism,
+        }
     }
 }
 
-pub struct SusState {
-    pub population: Rc<RefCell<PopU8>>,
-    pub alt_population: Rc<RefCell<PopU8>>,
+pub fn selection_stage<S, T, R>(getter: Getter<S, SelectionState<T>>) -> Stage<S, R>
+    where R: Rng,
+          T: Copy + 'static,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let selection_state = getter(state);
+        let num_inds = selection_state.alt_population.borrow().0.len();
+
+        let mut fitnesses = selection_state.fitnesses.borrow_mut();
+        let selected = select_indices(selection_state.selection, &fitnesses, num_inds, selection_state.elitism, rng);
+        copy_selected(&selection_state.population.borrow(), &mut selection_state.alt_population.borrow_mut(), &selected);
+        *fitnesses = selected_fitnesses(&fitnesses, &selected);
+
+        selection_state.population.swap(&selection_state.alt_population);
+    });
+
+    f
+}
+
+pub struct SusState<T> {
+    pub population: Rc<RefCell<Pop<T>>>,
+    pub alt_population: Rc<RefCell<Pop<T>>>,
     pub fitnesses: Rc<RefCell<Vec<f64>>>,
     pub elitism: usize,
 }
 
-impl SusState {
-    pub fn new(population: Rc<RefCell<PopU8>>,
-               alt_population: Rc<RefCell<PopU8>>,
+impl<T> SusState<T> {
+    pub fn new(population: Rc<RefCell<Pop<T>>>,
+               alt_population: Rc<RefCell<Pop<T>>>,
                fitnesses: Rc<RefCell<Vec<f64>>>,
-               elitism: usize) -> SusState {
+               elitism: usize) -> SusState<T> {
         return SusState {
             population,
             alt_population,
@@ -95,16 +382,20 @@ impl SusState {
     }
 }
 
-pub fn sus_stage<S, R>(getter: Getter<S, SusState>) -> Stage<S, R>
+// Select into the alternate population, then swap so that the population
+// holds the next generation.
+pub fn sus_stage<S, T, R>(getter: Getter<S, SusState<T>>) -> Stage<S, R>
     where R: Rng,
+          T: Copy + 'static,
           S: 'static {
     let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
-        let mut sus_state = getter(state);
-        stochastic_universal_sampling(&mut sus_state.population.borrow_mut(),
+        let sus_state = getter(state);
+        stochastic_universal_sampling(&sus_state.population.borrow(),
                                       &mut sus_state.alt_population.borrow_mut(),
-                                      &sus_state.fitnesses.borrow_mut(),
+                                      &sus_state.fitnesses.borrow(),
                                       sus_state.elitism,
                                       rng);
+        sus_state.population.swap(&sus_state.alt_population);
     });
 
     return f;
@@ -168,6 +459,8 @@ pub fn select_stochastic_universal_naive<T>(pop: &Pop<T>, fitnesses: &Vec<f64>,
 
 pub fn select_stochastic_universal<T>(pop: &Pop<T>, new_pop: &mut Pop<T>, fitnesses: &Vec<f64>, elitism: usize, offset_scaler: f64) 
     where T: Copy {
+    let num_inds = pop.0.len();
+
     let total_fitness = fitnesses.iter().sum::<f64>();
     assert!(total_fitness != 0.0, "Cannot sample when all fitness values are 0.0!");
 
@@ -183,7 +476,7 @@ pub fn select_stochastic_universal<T>(pop: &Pop<T>, new_pop: &mut Pop<T>, fitnes
     // elitism- give a certain number of individuals a free pass to the next generation
     let mut elite_indices = k_elite(&fitnesses, elitism);
 
-    while num_selections < pop.0.len() {
+    while num_selections < num_inds && ind_index < num_inds {
         accum_fitness += fitnesses[ind_index];
 
         // if we are going to skip this individual, check if they are elite
@@ -192,8 +485,8 @@ pub fn select_stochastic_universal<T>(pop: &Pop<T>, new_pop: &mut Pop<T>, fitnes
             let elite_pos = elite_indices.iter().position(|index| *index == ind_index);
             if elite_pos.is_some() {
                 // put the individual in the new population
-                new_pop.0[ind_index].0.clear();
-                new_pop.0[ind_index].0.extend(pop.0[ind_index].0.iter());
+                new_pop.0[num_selections].0.clear();
+                new_pop.0[num_selections].0.extend(pop.0[ind_index].0.iter());
                 // remove the individual from the elite array, just to make it smaller
                 // for subsequent checks.
                 elite_indices.swap_remove(elite_pos.unwrap());
@@ -201,14 +494,136 @@ pub fn select_stochastic_universal<T>(pop: &Pop<T>, new_pop: &mut Pop<T>, fitnes
             }
         }
 
-        while offset <= accum_fitness {
-            new_pop.0[ind_index].0.clear();
-            new_pop.0[ind_index].0.extend(pop.0[ind_index].0.iter());
+        while offset <= accum_fitness && num_selections < num_inds {
+            new_pop.0[num_selections].0.clear();
+            new_pop.0[num_selections].0.extend(pop.0[ind_index].0.iter());
             offset += increment;
             num_selections += 1;
         }
 
         ind_index += 1;
     }
+
+    // rounding in the accumulated fitness can leave the last pointer just past
+    // the end of the wheel. Fill any remaining slots with the last individual.
+    while num_selections < num_inds {
+        new_pop.0[num_selections].0.clear();
+        new_pop.0[num_selections].0.extend(pop.0[num_inds - 1].0.iter());
+        num_selections += 1;
+    }
+}
+
+// The indices chosen by select_stochastic_universal, with num_select pointers on the wheel.
+pub fn stochastic_universal_indices(fitnesses: &[f64], num_select: usize, elitism: usize, offset_scaler: f64) -> Vec<usize> {
+    let num_inds = fitnesses.len();
+    let mut selected = Vec::with_capacity(num_select);
+
+    let total_fitness = fitnesses.iter().sum::<f64>();
+    assert!(total_fitness != 0.0, "Cannot sample when all fitness values are 0.0!");
+
+    let increment = total_fitness / num_select as f64;
+    assert!(increment.is_normal(), "Selection cannot work with {} increment!", increment);
+
+    let mut offset = increment * offset_scaler;
+    let mut accum_fitness = 0.0;
+    let mut ind_index = 0;
+
+    let mut elite_indices = k_elite(fitnesses, elitism);
+
+    while selected.len() < num_select && ind_index < num_inds {
+        accum_fitness += fitnesses[ind_index];
+
+        // skipped individuals are still selected if they are elite
+        if offset > accum_fitness {
+            if let Some(elite_pos) = elite_indices.iter().position(|index| *index == ind_index) {
+                selected.push(ind_index);
+                elite_indices.swap_remove(elite_pos);
+            }
+        }
+
+        while offset <= accum_fitness && selected.len() < num_select {
+            selected.push(ind_index);
+            offset += increment;
+        }
+
+        ind_index += 1;
+    }
+
+    while selected.len() < num_select {
+        selected.push(num_inds - 1);
+    }
+
+    selected
 }
 
+#[test]
+fn test_select_stochastic_universal_copies_selected() {
+    let pop = Pop(vec!(Ind(vec!(0)), Ind(vec!(1)), Ind(vec!(2)), Ind(vec!(3))));
+    let mut new_pop = Pop(vec!(Ind(vec!(0)), Ind(vec!(0)), Ind(vec!(0)), Ind(vec!(0))));
+    let fitnesses = vec!(0.0, 0.0, 1.0, 0.0);
+
+    select_stochastic_universal(&pop, &mut new_pop, &fitnesses, 0, 0.5);
+
+    assert!(new_pop.0.iter().all(|ind| *ind == Ind(vec!(2))), "{:?}", new_pop);
+}
+
+#[test]
+fn test_tournament_selection_fills_new_pop() {
+    let pop = Pop(vec!(Ind(vec!(0)), Ind(vec!(1)), Ind(vec!(2)), Ind(vec!(3))));
+    let mut new_pop = Pop(vec!(Ind(vec!(9)), Ind(vec!(9)), Ind(vec!(9)), Ind(vec!(9))));
+    let fitnesses = vec!(1.0, 4.0, 2.0, 3.0);
+    let mut rng = R::seed_from_u64(0);
+
+    // with large tournaments that the fittest always wins, individual 1 is picked every time
+    tournament_selection(&pop, &mut new_pop, &fitnesses, 1.0, 100, 0, &mut rng);
+    assert!(new_pop.0.iter().all(|ind| *ind == Ind(vec!(1))), "{:?}", new_pop);
+
+    let selected = tournament_indices(&fitnesses, 4, 0.75, 2, 0, &mut rng);
+    assert_eq!(selected.len(), 4);
+    assert!(selected.iter().all(|index| *index < 4));
+}
+
+#[test]
+fn test_rank_weights() {
+    use float_cmp::ApproxEq;
+
+    // negative fitnesses are fine, as only their order matters
+    let fitnesses = vec!(-1.0, 10.0, 3.0, -5.0);
+
+    let weights = linear_rank_weights(&fitnesses, 2.0);
+    assert!(weights.iter().sum::<f64>().approx_eq(&1.0, 2.0 * ::std::f64::EPSILON, 2), "{:?}", weights);
+    assert_eq!(weights[3], 0.0);
+    assert!(weights[3] < weights[0] && weights[0] < weights[2] && weights[2] < weights[1]);
+
+    let weights = exponential_rank_weights(&fitnesses, 0.5);
+    assert_eq!(weights, vec!(0.25, 1.0, 0.5, 0.125));
+}
+
+#[test]
+fn test_k_elite_orders_by_fitness() {
+    let fitnesses = vec!(3.0, 1.0, 5.0, 4.0, 2.0);
+
+    assert_eq!(k_elite(&fitnesses, 3), vec!(2, 3, 0));
+    assert_eq!(k_elite(&fitnesses, 0), Vec::<usize>::new());
+}
+
+#[test]
+fn test_select_indices_matches_select() {
+    let pop = Pop(vec!(Ind(vec!(0)), Ind(vec!(1)), Ind(vec!(2)), Ind(vec!(3)), Ind(vec!(4))));
+    let fitnesses = vec!(1.0, 4.0, 0.5, 3.0, 2.0);
+
+    for selection in vec!(Selection::StochasticUniversal,
+                          Selection::Tournament { size: 2, prob: 0.8 },
+                          Selection::LinearRank { pressure: 1.5 },
+                          Selection::ExponentialRank { base: 0.5 }) {
+        let mut new_pop = pop.clone();
+        select(selection, &pop, &mut new_pop, &fitnesses, 1, &mut R::seed_from_u64(7));
+
+        let selected = select_indices(selection, &fitnesses, pop.0.len(), 1, &mut R::seed_from_u64(7));
+        let mut indexed_pop = pop.clone();
+        copy_selected(&pop, &mut indexed_pop, &selected);
+
+        assert_eq!(new_pop, indexed_pop, "{:?}", selection);
+        assert_eq!(select_indices(selection, &fitnesses, 8, 1, &mut R::seed_from_u64(7)).len(), 8);
+    }
+}
@@ -1,4 +1,8 @@
 use std::rc::Rc;
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use rand::prelude::*;
 
 
 pub type Stage<State, R> = Rc<dyn Fn(&State, &mut R)>;
@@ -16,5 +20,187 @@ pub fn compose_stages<S, R>(stage1: Stage<S, R>, stage2: Stage<S, R>) -> Stage<S
     return f;
 }
 
+// apply transformer2 to the result of transformer1
+pub fn compose_transformers<S, R>(transformer1: StageTransformer<S, R>, transformer2: StageTransformer<S, R>) -> StageTransformer<S, R>
+    where S: 'static,
+          R: 'static {
+    let f: StageTransformer<S, R> = Rc::new(move |stage| {
+        transformer2(transformer1(stage))
+    });
+
+    f
+}
+
+// Run the stage on the first call and then on every nth call after that.
+// Each stage produced by the transformer keeps its own count.
+pub fn every_n<S, R>(n: usize) -> StageTransformer<S, R>
+    where S: 'static,
+          R: 'static {
+    assert!(n > 0, "A stage cannot be run every 0 generations!");
+
+    let f: StageTransformer<S, R> = Rc::new(move |stage| {
+        let count = Rc::new(Cell::new(0usize));
+        let g: Stage<S, R> = Rc::new(move |state, rng| {
+            if count.get() % n == 0 {
+                stage(state, rng);
+            }
+            count.set(count.get() + 1);
+        });
+        g
+    });
+
+    f
+}
+
+pub fn with_probability<S, R>(prob: f64) -> StageTransformer<S, R>
+    where S: 'static,
+          R: Rng + 'static {
+    let f: StageTransformer<S, R> = Rc::new(move |stage| {
+        let g: Stage<S, R> = Rc::new(move |state, rng| {
+            if rng.gen::<f64>() < prob {
+                stage(state, rng);
+            }
+        });
+        g
+    });
+
+    f
+}
+
+pub fn repeat<S, R>(times: usize) -> StageTransformer<S, R>
+    where S: 'static,
+          R: 'static {
+    let f: StageTransformer<S, R> = Rc::new(move |stage| {
+        let g: Stage<S, R> = Rc::new(move |state, rng| {
+            for _ in 0..times {
+                stage(state, rng);
+            }
+        });
+        g
+    });
+
+    f
+}
+
+// run the stage only when the predicate holds for the current state
+pub fn run_while<S, R>(predicate: Rc<dyn Fn(&S) -> bool>) -> StageTransformer<S, R>
+    where S: 'static,
+          R: 'static {
+    let f: StageTransformer<S, R> = Rc::new(move |stage| {
+        let predicate = predicate.clone();
+        let g: Stage<S, R> = Rc::new(move |state, rng| {
+            if predicate(state) {
+                stage(state, rng);
+            }
+        });
+        g
+    });
+
+    f
+}
+
+// add the time spent in the stage to elapsed
+pub fn timed<S, R>(elapsed: Rc<Cell<Duration>>) -> StageTransformer<S, R>
+    where S: 'static,
+          R: 'static {
+    let f: StageTransformer<S, R> = Rc::new(move |stage| {
+        let elapsed = elapsed.clone();
+        let g: Stage<S, R> = Rc::new(move |state, rng| {
+            let start = Instant::now();
+            stage(state, rng);
+            elapsed.set(elapsed.get() + start.elapsed());
+        });
+        g
+    });
+
+    f
+}
+
+// Pass a line to log when the stage starts and when it finishes, such as
+// Rc::new(|line: &str| println!("{}", line)) to print them.
+pub fn logged<S, R>(name: &str, log: Rc<dyn Fn(&str)>) -> StageTransformer<S, R>
+    where S: 'static,
+          R: 'static {
+    let name = name.to_string();
+    let f: StageTransformer<S, R> = Rc::new(move |stage| {
+        let name = name.clone();
+        let log = log.clone();
+        let g: Stage<S, R> = Rc::new(move |state, rng| {
+            log(&format!("stage {} starting", name));
+            let start = Instant::now();
+            stage(state, rng);
+            log(&format!("stage {} finished in {:?}", name, start.elapsed()));
+        });
+        g
+    });
+
+    f
+}
+
 pub type Getter<S, D> = Rc<dyn Fn(&S) -> D>;
 
+#[test]
+fn test_stage_transformers() {
+    use std::cell::RefCell;
+    use rand::rngs::SmallRng;
+
+    let count_stage: Stage<Cell<usize>, SmallRng> = Rc::new(|count, _rng| {
+        count.set(count.get() + 1);
+    });
+
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    let every_third = every_n(3)(count_stage.clone());
+    let count = Cell::new(0);
+    for _ in 0..7 {
+        every_third(&count, &mut rng);
+    }
+    assert_eq!(count.get(), 3);
+
+    let twice_every_third = compose_transformers(repeat(2), every_n(3))(count_stage.clone());
+    let count = Cell::new(0);
+    for _ in 0..7 {
+        twice_every_third(&count, &mut rng);
+    }
+    assert_eq!(count.get(), 6);
+
+    let below_five = run_while(Rc::new(|count: &Cell<usize>| count.get() < 5))(count_stage.clone());
+    let count = Cell::new(0);
+    for _ in 0..10 {
+        below_five(&count, &mut rng);
+    }
+    assert_eq!(count.get(), 5);
+
+    let never = with_probability(0.0)(count_stage.clone());
+    let count = Cell::new(0);
+    never(&count, &mut rng);
+    assert_eq!(count.get(), 0);
+
+    // the time spent in the stage accumulates across calls
+    let sleep_stage: Stage<Cell<usize>, SmallRng> = Rc::new(|count, _rng| {
+        std::thread::sleep(Duration::from_millis(1));
+        count.set(count.get() + 1);
+    });
+
+    let elapsed = Rc::new(Cell::new(Duration::from_secs(0)));
+    let timed_stage = timed(elapsed.clone())(sleep_stage.clone());
+    let count = Cell::new(0);
+    timed_stage(&count, &mut rng);
+    assert_eq!(count.get(), 1);
+    let first_elapsed = elapsed.get();
+    assert!(first_elapsed >= Duration::from_millis(1), "{:?}", first_elapsed);
+    timed_stage(&count, &mut rng);
+    assert!(elapsed.get() >= first_elapsed + Duration::from_millis(1), "{:?}", elapsed.get());
+
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let log_lines = lines.clone();
+    let logged_stage = logged("count", Rc::new(move |line: &str| log_lines.borrow_mut().push(line.to_string())))(count_stage.clone());
+    let count = Cell::new(0);
+    logged_stage(&count, &mut rng);
+    assert_eq!(count.get(), 1);
+
+    let lines = lines.borrow();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "stage count starting");
+    assert!(lines[1].starts_with("stage count finished in "), "{}", lines[1]);
+}
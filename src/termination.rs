@@ -0,0 +1,142 @@
+use std::time::{Duration, Instant};
+
+
+// A condition that ends a run early. The generation limit from the
+// run's parameters always applies in addition to these.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Criterion {
+    TargetFitness(f64),
+    Stagnation(usize),
+    TimeLimit(Duration),
+    MaxEvaluations(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    MaxGenerations,
+    TargetFitness,
+    Stagnation,
+    TimeLimit,
+    MaxEvaluations,
+    Observer,
+    // the caller ended an ask/tell run before any criterion was met
+    External,
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Termination {
+    pub criteria: Vec<Criterion>,
+}
+
+impl Termination {
+    pub fn new() -> Termination {
+        Termination { criteria: Vec::new() }
+    }
+
+    pub fn with(mut self, criterion: Criterion) -> Termination {
+        self.criteria.push(criterion);
+        self
+    }
+
+    // criteria are checked in the order they were given, with the generation
+    // limit checked last.
+    pub fn check(&self, num_gens: usize, progress: &Progress) -> Option<StopReason> {
+        for criterion in self.criteria.iter() {
+            match criterion {
+                Criterion::TargetFitness(target) => {
+                    if progress.best_fitness >= *target {
+                        return Some(StopReason::TargetFitness);
+                    }
+                },
+
+                Criterion::Stagnation(num_stagnant) => {
+                    if progress.stagnant_gens >= *num_stagnant {
+                        return Some(StopReason::Stagnation);
+                    }
+                },
+
+                Criterion::TimeLimit(limit) => {
+                    if progress.start.elapsed() >= *limit {
+                        return Some(StopReason::TimeLimit);
+                    }
+                },
+
+                Criterion::MaxEvaluations(max_evaluations) => {
+                    if progress.evaluations >= *max_evaluations {
+                        return Some(StopReason::MaxEvaluations);
+                    }
+                },
+            }
+        }
+
+        if progress.generation >= num_gens {
+            return Some(StopReason::MaxGenerations);
+        }
+
+        None
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub generation: usize,
+    pub evaluations: usize,
+    pub best_fitness: f64,
+    pub stagnant_gens: usize,
+    pub start: Instant,
+}
+
+impl Progress {
+    pub fn new() -> Progress {
+        Progress {
+            generation: 0,
+            evaluations: 0,
+            best_fitness: f64::NEG_INFINITY,
+            stagnant_gens: 0,
+            start: Instant::now(),
+        }
+    }
+
+    // record a finished generation, returning whether it improved on the best fitness
+    pub fn update(&mut self, gen_best_fitness: f64, num_evaluations: usize) -> bool {
+        self.generation += 1;
+        self.evaluations += num_evaluations;
+
+        let improved = gen_best_fitness > self.best_fitness;
+        if improved {
+            self.best_fitness = gen_best_fitness;
+            self.stagnant_gens = 0;
+        } else {
+            self.stagnant_gens += 1;
+        }
+
+        improved
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Progress {
+        Progress::new()
+    }
+}
+
+#[test]
+fn test_termination_check() {
+    let termination = Termination::new().with(Criterion::TargetFitness(10.0))
+                                        .with(Criterion::Stagnation(2))
+                                        .with(Criterion::MaxEvaluations(1000));
+
+    let mut progress = Progress::new();
+
+    progress.update(1.0, 100);
+    assert_eq!(termination.check(100, &progress), None);
+
+    progress.update(1.0, 100);
+    progress.update(0.5, 100);
+    assert_eq!(termination.check(100, &progress), Some(StopReason::Stagnation));
+
+    progress.update(10.0, 100);
+    assert_eq!(termination.check(100, &progress), Some(StopReason::TargetFitness));
+
+    assert_eq!(Termination::new().check(4, &progress), Some(StopReason::MaxGenerations));
+}
@@ -0,0 +1,22 @@
+use types::*;
+use observer::*;
+use hall_of_fame::*;
+
+
+// An observer checking that the best of an archive kept from the evaluated populations is
+// the last individual of each following generation, as it is when reinserted.
+pub struct ReinsertionObserver<T> {
+    pub hall_of_fame: HallOfFame<T>,
+}
+
+impl<T: Clone + PartialEq + std::fmt::Debug> Observer<T> for ReinsertionObserver<T> {
+    fn on_generation(&mut self, generation: usize, pop: &Pop<T>, fitnesses: &Vec<f64>) -> Control {
+        if let Some(best) = self.hall_of_fame.best() {
+            assert_eq!(pop.0.last(), Some(&best.ind));
+            assert_eq!(*fitnesses.last().unwrap(), best.fitness);
+        }
+
+        self.hall_of_fame.update(pop, fitnesses, generation);
+        Control::Continue
+    }
+}
@@ -0,0 +1,278 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use rand::prelude::*;
+
+use types::*;
+use stage::*;
+
+
+// Transposition operators from Gene Expression Programming, working on whole RGEP symbols.
+// GEP reads its genes from the root at the start, while RGEP programs are postfix and
+// leave their result from the last symbol, so here the root is at the end of an
+// individual. Each operator keeps individuals the same length, dropping symbols from the
+// start, furthest from the root, to make room.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TranspositionParams {
+    // the probability of each individual having an insertion sequence transposed, a root
+    // insertion sequence transposed, and a gene transposed
+    pub prob_is: f64,
+    pub prob_ris: f64,
+    pub prob_gene: f64,
+
+    // the longest segment copied by IS and RIS transposition
+    pub max_length: usize,
+
+    // the number of symbols in each gene, for gene transposition
+    pub gene_size: usize,
+}
+
+impl Default for TranspositionParams {
+    fn default() -> TranspositionParams {
+        TranspositionParams {
+            prob_is: 0.0,
+            prob_ris: 0.0,
+            prob_gene: 0.0,
+            max_length: 3,
+            gene_size: 10,
+        }
+    }
+}
+
+// RGEP codes with a low bit of 1 decode to functions, and all others to terminals
+pub fn is_function_code(code: u8) -> bool {
+    (code & 1) == 1
+}
+
+// Insert a copy of the length symbols starting at start before the symbol at target, and
+// then drop length symbols from the start of the individual.
+pub fn transpose_is(ind: &mut IndU8, start: usize, length: usize, target: usize) {
+    let segment: Vec<u8> = ind.0[start..(start + length)].to_vec();
+    ind.0.splice(target..target, segment);
+    ind.0.drain(0..length);
+}
+
+// Copy the segment of up to length symbols ending with the function at or before position
+// to the end of the individual, so the function becomes the root, and then drop as many
+// symbols from the start. Does nothing if there is no function at or before position.
+pub fn transpose_ris(ind: &mut IndU8, position: usize, length: usize) {
+    if let Some(end) = (0..=position).rev().find(|index| is_function_code(ind.0[*index])) {
+        let start = (end + 1).saturating_sub(length);
+        let segment: Vec<u8> = ind.0[start..=end].to_vec();
+        let segment_len = segment.len();
+
+        ind.0.extend(segment);
+        ind.0.drain(0..segment_len);
+    }
+}
+
+// Move the gene at gene_index after the other whole genes of gene_size symbols. Any symbols
+// after the last whole gene stay where they are.
+pub fn transpose_gene(ind: &mut IndU8, gene_size: usize, gene_index: usize) {
+    let num_genes = ind.0.len() / gene_size;
+    let gene: Vec<u8> = ind.0.drain((gene_index * gene_size)..((gene_index + 1) * gene_size)).collect();
+    let target = (num_genes - 1) * gene_size;
+    ind.0.splice(target..target, gene);
+}
+
+// IS transposition of each individual with probability prob, copying a segment of 1 to
+// max_length symbols from a random position to another random position before the root
+pub fn is_transposition<R: Rng>(pop: &mut PopU8, max_length: usize, prob: f64, rng: &mut R) {
+    assert!(max_length > 0, "Transposed segments must have at least one symbol!");
+
+    for ind in pop.0.iter_mut() {
+        let ind_len = ind.0.len();
+        if rng.gen::<f64>() < prob && ind_len > 1 {
+            let length = rng.gen_range(1, max_length + 1).min(ind_len - 1);
+            let start = rng.gen_range(0, ind_len - length + 1);
+            let target = rng.gen_range(0, ind_len);
+            transpose_is(ind, start, length, target);
+        }
+    }
+}
+
+// RIS transposition of each individual with probability prob, copying a segment of 1 to
+// max_length symbols that ends with a function to the root
+pub fn ris_transposition<R: Rng>(pop: &mut PopU8, max_length: usize, prob: f64, rng: &mut R) {
+    assert!(max_length > 0, "Transposed segments must have at least one symbol!");
+
+    for ind in pop.0.iter_mut() {
+        let ind_len = ind.0.len();
+        if rng.gen::<f64>() < prob && ind_len > 1 {
+            let length = rng.gen_range(1, max_length + 1).min(ind_len - 1);
+            let position = rng.gen_range(0, ind_len);
+            transpose_ris(ind, position, length);
+        }
+    }
+}
+
+// Gene transposition of each individual with probability prob, moving a random gene other
+// than the last to the root. Individuals with fewer than two genes are left alone.
+pub fn gene_transposition<R: Rng>(pop: &mut PopU8, gene_size: usize, prob: f64, rng: &mut R) {
+    assert!(gene_size > 0, "Genes must have at least one symbol!");
+
+    for ind in pop.0.iter_mut() {
+        let num_genes = ind.0.len() / gene_size;
+        if rng.gen::<f64>() < prob && num_genes > 1 {
+            let gene_index = rng.gen_range(0, num_genes - 1);
+            transpose_gene(ind, gene_size, gene_index);
+        }
+    }
+}
+
+// apply IS, RIS and gene transposition with the probabilities in transposition, skipping
+// any operator whose probability is 0
+pub fn transposition<R: Rng>(pop: &mut PopU8, transposition: &TranspositionParams, rng: &mut R) {
+    if transposition.prob_is > 0.0 {
+        is_transposition(pop, transposition.max_length, transposition.prob_is, rng);
+    }
+
+    if transposition.prob_ris > 0.0 {
+        ris_transposition(pop, transposition.max_length, transposition.prob_ris, rng);
+    }
+
+    if transposition.prob_gene > 0.0 {
+        gene_transposition(pop, transposition.gene_size, transposition.prob_gene, rng);
+    }
+}
+
+// shared by the IS and RIS transposition stages
+pub struct TranspositionState {
+    pub population: Rc<RefCell<PopU8>>,
+    pub prob: f64,
+    pub max_length: usize,
+}
+
+impl TranspositionState {
+    pub fn new(population: Rc<RefCell<PopU8>>,
+               prob: f64,
+               max_length: usize) -> TranspositionState {
+        TranspositionState { population, prob, max_length }
+    }
+}
+
+pub fn is_transposition_stage<S, R>(getter: Getter<S, TranspositionState>) -> Stage<S, R>
+    where R: Rng,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let transposition_state = getter(state);
+        is_transposition(&mut transposition_state.population.borrow_mut(),
+                         transposition_state.max_length,
+                         transposition_state.prob,
+                         rng);
+    });
+
+    f
+}
+
+pub fn ris_transposition_stage<S, R>(getter: Getter<S, TranspositionState>) -> Stage<S, R>
+    where R: Rng,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let transposition_state = getter(state);
+        ris_transposition(&mut transposition_state.population.borrow_mut(),
+                          transposition_state.max_length,
+                          transposition_state.prob,
+                          rng);
+    });
+
+    f
+}
+
+pub struct GeneTranspositionState {
+    pub population: Rc<RefCell<PopU8>>,
+    pub prob: f64,
+    pub gene_size: usize,
+}
+
+impl GeneTranspositionState {
+    pub fn new(population: Rc<RefCell<PopU8>>,
+               prob: f64,
+               gene_size: usize) -> GeneTranspositionState {
+        GeneTranspositionState { population, prob, gene_size }
+    }
+}
+
+pub fn gene_transposition_stage<S, R>(getter: Getter<S, GeneTranspositionState>) -> Stage<S, R>
+    where R: Rng,
+          S: 'static {
+    let f: Rc<dyn Fn(&S, &mut R)> = Rc::new(move |state, rng| {
+        let transposition_state = getter(state);
+        gene_transposition(&mut transposition_state.population.borrow_mut(),
+                           transposition_state.gene_size,
+                           transposition_state.prob,
+                           rng);
+    });
+
+    f
+}
+
+#[test]
+fn test_transposition() {
+    // even codes are terminals and odd codes are functions
+    let mut ind = Ind(vec!(0, 2, 4, 6, 8, 10));
+    transpose_is(&mut ind, 1, 2, 5);
+    assert_eq!(ind, Ind(vec!(4, 6, 8, 2, 4, 10)));
+
+    let mut ind = Ind(vec!(0, 2, 3, 4, 6, 8));
+    transpose_ris(&mut ind, 4, 2);
+    assert_eq!(ind, Ind(vec!(3, 4, 6, 8, 2, 3)));
+
+    // without a function there is nothing to transpose
+    let mut ind = Ind(vec!(0, 2, 4, 6));
+    transpose_ris(&mut ind, 3, 2);
+    assert_eq!(ind, Ind(vec!(0, 2, 4, 6)));
+
+    let mut ind = Ind(vec!(0, 0, 1, 1, 2, 2, 9));
+    transpose_gene(&mut ind, 2, 0);
+    assert_eq!(ind, Ind(vec!(1, 1, 2, 2, 0, 0, 9)));
+
+    let mut rng = R::seed_from_u64(0);
+
+    // each operator keeps the length, and leaves its donor segment at its target: IS before
+    // the symbol that was at target, RIS at the root, and a gene after the other whole genes
+    let original = Ind((0..20).collect::<Vec<u8>>());
+    for _ in 0..100 {
+        let length: usize = rng.gen_range(1, 5);
+        let start = rng.gen_range(0, 20 - length + 1);
+        let target = rng.gen_range(length, 20);
+        let mut ind = original.clone();
+        transpose_is(&mut ind, start, length, target);
+        assert_eq!(ind.0.len(), 20);
+        assert_eq!(&ind.0[(target - length)..target], &original.0[start..(start + length)]);
+        assert_eq!(&ind.0[target..], &original.0[target..]);
+
+        // odd codes are functions, so the segment ends at the last odd code at or before position
+        let position: usize = rng.gen_range(1, 20);
+        let end = if position % 2 == 1 { position } else { position - 1 };
+        let segment = &original.0[(end + 1).saturating_sub(length)..=end];
+        let mut ind = original.clone();
+        transpose_ris(&mut ind, position, length);
+        assert_eq!(ind.0.len(), 20);
+        assert_eq!(&ind.0[(20 - segment.len())..], segment);
+
+        // 6 whole genes of 3 symbols, so the moved gene ends up at 15 to 18
+        let gene_index = rng.gen_range(0, 6);
+        let mut ind = original.clone();
+        transpose_gene(&mut ind, 3, gene_index);
+        assert_eq!(ind.0.len(), 20);
+        assert_eq!(&ind.0[15..18], &original.0[(gene_index * 3)..((gene_index + 1) * 3)]);
+        assert_eq!(&ind.0[18..], &original.0[18..]);
+    }
+
+    let mut pop = Pop(vec![Ind(vec!(0, 1, 2, 3, 4, 5, 6, 7)); 10]);
+    let params = TranspositionParams { prob_is: 1.0, prob_ris: 1.0, prob_gene: 1.0, max_length: 3, gene_size: 4 };
+    for _ in 0..20 {
+        transposition(&mut pop, &params, &mut rng);
+        assert!(pop.0.iter().all(|ind| ind.0.len() == 8));
+        assert!(pop.0.iter().all(|ind| ind.0.iter().all(|code| *code < 8)));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_is_transposition_max_length() {
+    let mut rng = R::seed_from_u64(0);
+    let mut pop = Pop(vec![Ind(vec!(0, 1, 2, 3)); 2]);
+    is_transposition(&mut pop, 0, 1.0, &mut rng);
+}